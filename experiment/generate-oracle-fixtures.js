@@ -0,0 +1,271 @@
+// Generates the token fixtures of the Rust codec tests (payment-channel-token/tests/oracle_fixtures.rs):
+// drives the oracle (payment-channel-service/oracle.js) through open, join, update and leave of a
+// channel and records the tokens it returns. IPFS, the chain and the sequence store are replaced
+// by in-memory stand-ins; keys are derived from fixed seeds, so the output is reproducible.
+//
+// node experiment/generate-oracle-fixtures.js
+
+const fs = require("fs");
+const os = require("os");
+const path = require("path");
+
+const Web3 = require("@solana/web3.js");
+const Base58 = require("base-58");
+const Nacl = require("tweetnacl");
+const borsh = require("borsh");
+
+const service_dir = path.join(__dirname, "../payment-channel-service");
+const fixtures_path = path.join(__dirname, "off-chain-txs-data/oracle-fixtures.json");
+
+// FIXTURE PARAMETERS (for testing purposes only)
+const program_id = "PaymentChanne111111111111111111111111111111";
+const oracle_keypair = Nacl.sign.keyPair.fromSeed(new Uint8Array(32).fill(1));
+const stakeholder_a_keypair = Nacl.sign.keyPair.fromSeed(new Uint8Array(32).fill(2));
+const stakeholder_b_keypair = Nacl.sign.keyPair.fromSeed(new Uint8Array(32).fill(3));
+const channel_id = "fixture-channel";
+const open_amount = 1000;
+const join_amount = 500;
+const tx_amount = 100;
+const emergency_timeout_slots = 216000;
+
+class Assignable {
+    constructor(properties) {
+        Object.keys(properties).map((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class Data extends Assignable {
+}
+
+// In-memory chain: channel PDA (leading fields of ChannelState) and NONCE PDA of the channel
+const accounts = new Map();
+
+const connection = {
+    getAccountInfo: async (pubkey) => {
+        const data = accounts.get(pubkey.toBase58());
+        return data === undefined ? null : { data };
+    }
+};
+
+const channel_state_data = (num_of_stakeholders, sequence) => {
+    const schema = new Map([[Data, {
+        kind: 'struct',
+        fields: [
+            ['channel_id', 'string'],
+            ['oracle_addresses', [[32]]],
+            ['oracle_threshold', 'u8'],
+            ['current_status', 'u8'],
+            ['num_of_stakeholders', 'u8'],
+            ['sequence', 'u64'],
+        ]
+    }]]);
+
+    return Buffer.from(borsh.serialize(schema, new Data({
+        channel_id,
+        oracle_addresses: [oracle_keypair.publicKey],
+        oracle_threshold: 1,
+        current_status: 1,
+        num_of_stakeholders,
+        sequence
+    })));
+}
+
+const channel_nonce_data = (nonce) => {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt(nonce));
+    return data;
+}
+
+// In-memory IPFS / IPNS: tokens stored by the oracle, latest one per channel
+const ipfs_store = [];
+const ipns_records = new Map();
+
+const ipfs_helper = {
+    check_ipns_key_existence: async (channelid) => ipns_records.has(channelid),
+    create_ipns_key: async (channelid) => channelid,
+    store_data_ipfs: async (data) => {
+        ipfs_store.push(Buffer.from(data));
+        return "/ipfs/fixture-cid-" + ipfs_store.length;
+    },
+    publish_ipns: async (channelid, cid) => {
+        ipns_records.set(channelid, cid);
+    },
+    resolve_cid_ipns: async (channelid) => ipns_records.get(channelid)
+};
+
+// Off-chain state of the channel as the state helper would rebuild it from IPFS
+let channel_state = [];
+
+const state_helper = {
+    get_last_channel_state: async () => channel_state.map((item) => ({ ...item }))
+};
+
+const sequence_store_path = path.join(fs.mkdtempSync(path.join(os.tmpdir(), "oracle-fixtures-")), "sequence-store.json");
+
+const configuration = {
+    program_id,
+    oracle_private_key: Array.from(oracle_keypair.secretKey),
+    oracle_public_key: Base58.encode(oracle_keypair.publicKey),
+    oracle_address: Base58.encode(oracle_keypair.publicKey),
+    oracle_key_version: 0,
+    connection,
+    sequence_store_path
+};
+
+const stub_module = (file, exports) => {
+    const filename = path.join(service_dir, file);
+    require.cache[filename] = { id: filename, filename, loaded: true, exports };
+}
+
+stub_module("configuration.js", configuration);
+stub_module("utils/ipfs-helper-oracle.js", ipfs_helper);
+stub_module("utils/state-helper-oracle.js", state_helper);
+
+const oracle = require(path.join(service_dir, "oracle.js"));
+
+const program_pubkey = new Web3.PublicKey(program_id);
+const pda_channel = Web3.PublicKey.findProgramAddressSync([Buffer.from(channel_id)], program_pubkey)[0];
+const pda_nonce = Web3.PublicKey.findProgramAddressSync([Buffer.from("nonce"), Buffer.from(channel_id)], program_pubkey)[0];
+
+const pre_token_schema = new Map([[Data, {
+    kind: 'struct',
+    fields: [
+        ['encoded_data', ["u8"]],
+        ['prev_state', 'string'],
+        ['sig_sender', [64]],
+    ]
+}]]);
+
+const balance_fields = [
+    ['action', 'u8'],
+    ['channelid', 'string'],
+    ['address', [32]],
+    ['balance', 'u64'],
+    ['sender', [32]],
+    ['sequence', 'u64'],
+    ['channel_nonce', 'u64'],
+];
+
+// Token as sent by the stakeholder to the oracle (open / join / update)
+const pre_token = (fields, core_data, keypair) => {
+    const encoded_data = Buffer.from(borsh.serialize(new Map([[Data, { kind: 'struct', fields }]]), new Data(core_data)));
+
+    return Buffer.from(borsh.serialize(pre_token_schema, new Data({
+        encoded_data,
+        prev_state: '0',
+        sig_sender: Nacl.sign.detached(encoded_data, keypair.secretKey)
+    })));
+}
+
+const main = async () => {
+    const a = stakeholder_a_keypair.publicKey;
+    const b = stakeholder_b_keypair.publicKey;
+    const tokens = [];
+
+    // OPEN
+    const open_fields = balance_fields.concat([
+        ['mint', [32]],
+        ['oracles', [[32]]],
+        ['oracle_threshold', 'u8'],
+        ['emergency_timeout_slots', 'u64'],
+    ]);
+    const open_token = await oracle.open(pre_token(open_fields, {
+        action: 1,
+        channelid: channel_id,
+        address: a,
+        balance: open_amount,
+        sender: a,
+        sequence: await oracle.next_sequence(channel_id),
+        channel_nonce: await oracle.channel_nonce(channel_id) + 1,
+        mint: new Uint8Array(32),
+        oracles: [oracle_keypair.publicKey],
+        oracle_threshold: 1,
+        emergency_timeout_slots
+    }, stakeholder_a_keypair));
+    tokens.push({ action: 1, token: Buffer.from(open_token).toString('hex') });
+
+    // Open token executed on-chain
+    accounts.set(pda_channel.toBase58(), channel_state_data(1, 1));
+    accounts.set(pda_nonce.toBase58(), channel_nonce_data(1));
+    channel_state = [{ address: Base58.encode(a), balance: open_amount }];
+
+    // JOIN
+    const join_token = await oracle.join(pre_token(balance_fields, {
+        action: 2,
+        channelid: channel_id,
+        address: b,
+        balance: join_amount,
+        sender: b,
+        sequence: await oracle.next_sequence(channel_id),
+        channel_nonce: await oracle.channel_nonce(channel_id)
+    }, stakeholder_b_keypair));
+    tokens.push({ action: 2, token: Buffer.from(join_token).toString('hex') });
+
+    // Join token executed on-chain
+    accounts.set(pda_channel.toBase58(), channel_state_data(2, 2));
+    channel_state.push({ address: Base58.encode(b), balance: join_amount });
+
+    // UPDATE (A pays B)
+    const update_fields = [
+        ['action', 'u8'],
+        ['channelid', 'string'],
+        ['address1', [32]],
+        ['balance1', 'u64'],
+        ['address2', [32]],
+        ['balance2', 'u64'],
+        ['sender', [32]],
+        ['sequence', 'u64'],
+        ['channel_nonce', 'u64'],
+        ['num_of_stakeholders', 'u8'],
+    ];
+    await oracle.update(pre_token(update_fields, {
+        action: 3,
+        channelid: channel_id,
+        address1: a,
+        balance1: open_amount - tx_amount,
+        address2: b,
+        balance2: join_amount + tx_amount,
+        sender: a,
+        sequence: await oracle.next_sequence(channel_id),
+        channel_nonce: await oracle.channel_nonce(channel_id),
+        num_of_stakeholders: 2
+    }, stakeholder_a_keypair), Base58.encode(a), tx_amount, Base58.encode(b));
+    // update only stores the token (IPFS)
+    tokens.push({ action: 3, token: ipfs_store[ipfs_store.length - 1].toString('hex') });
+
+    channel_state = [
+        { address: Base58.encode(a), balance: open_amount - tx_amount },
+        { address: Base58.encode(b), balance: join_amount + tx_amount }
+    ];
+
+    // LEAVE (B) - core data signed by the stakeholder, sig_sender appended
+    const leave_core_data = {
+        action: 4,
+        channelid: channel_id,
+        address: b,
+        balance: join_amount + tx_amount,
+        sender: b,
+        sequence: await oracle.next_sequence(channel_id),
+        channel_nonce: await oracle.channel_nonce(channel_id)
+    };
+    const leave_encoded_data = Buffer.from(borsh.serialize(new Map([[Data, { kind: 'struct', fields: balance_fields }]]), new Data(leave_core_data)));
+    const leave_token = await oracle.leave(Buffer.concat([
+        leave_encoded_data,
+        Buffer.from(Nacl.sign.detached(leave_encoded_data, stakeholder_b_keypair.secretKey))
+    ]));
+    tokens.push({ action: 4, token: Buffer.from(leave_token).toString('hex') });
+
+    const fixtures = {
+        program_id,
+        oracle: Base58.encode(oracle_keypair.publicKey),
+        channel_id,
+        tokens
+    };
+
+    fs.writeFileSync(fixtures_path, JSON.stringify(fixtures, null, 2) + "\n");
+    console.log("Fixtures written to", fixtures_path);
+}
+
+main();
@@ -6,6 +6,12 @@ const program_id = 'insert program id here'
 const connection = new Web3.Connection(Web3.clusterApiUrl(network_name), 'confirmed')
 const oracle_public_key = // insert oracle public key here 
 
+// Key the oracle currently signs with (differs from oracle_public_key after RotateOracleKey)
+const oracle_signing_key = oracle_public_key;
+
+// Slots without oracle activity after which stakeholders can exit without the oracle (min 9000)
+const emergency_timeout_slots = 216000;
+
 const ipfs_config = {
     host: "insert IPFS host here",
     port: 'insert IPFS port here',
@@ -15,6 +21,8 @@ const ipfs_config = {
 module.exports = {
     program_id,
     oracle_public_key,
+    oracle_signing_key,
+    emergency_timeout_slots,
     connection,
     ipfs_config
 }
@@ -3,6 +3,7 @@ const borsh = require("borsh");
 const Base58 = require("base-58");
 
 const configuration = require("./configuration.js");
+const program_errors = require("./utils/program-errors-client.js");
 
 const connection = configuration.connection;
 
@@ -58,7 +59,7 @@ const main = async (channelid, stakeholder_keypair, invitee) => {
                 {
                     pubkey: stakeholder_keypair.publicKey,
                     isSigner: true,
-                    isWritable: false,
+                    isWritable: true, // pays the rent of the invitee PDA
                 },
                 {
                     pubkey: pda_channel[0],
@@ -93,6 +94,10 @@ const main = async (channelid, stakeholder_keypair, invitee) => {
 
     } catch (error) {
         console.log(error)
+        const program_error = program_errors.decode_program_error(error);
+        if (program_error) {
+            console.log("Program error " + program_error.code + ": " + program_error.name);
+        }
         return Promise.reject(error);
     }
 
@@ -4,6 +4,7 @@ const Nacl = require("tweetnacl");
 const Base58 = require("base-58");
 
 const configuration = require("./configuration.js");
+const program_errors = require("./utils/program-errors-client.js");
 
 const connection = configuration.connection;
 
@@ -14,6 +15,7 @@ const program_address = configuration.program_id;
 const program_pubkey = new Web3.PublicKey(program_address);
 
 const oracle_public_key = configuration.oracle_public_key;
+const oracle_signing_key = configuration.oracle_signing_key;
 
 class Assignable {
     constructor(properties) {
@@ -29,6 +31,9 @@ class InstructionPayload extends Assignable {
 class Data extends Assignable {
 }
 
+class OracleSignature extends Assignable {
+}
+
 
 const main = async (channelid, stakeholder_keypair, amount) => {
 
@@ -39,6 +44,8 @@ const main = async (channelid, stakeholder_keypair, amount) => {
 
         const prev_state = "0";
         const action = 2;
+        const sequence = await oracle_channel.next_sequence(channelid);
+        const channel_nonce = await oracle_channel.channel_nonce(channelid);
 
         const join_token_core_data_schema_sender = new Map([[Data, {
             kind: 'struct',
@@ -48,6 +55,8 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
             ]
         }]]);
 
@@ -68,6 +77,8 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
                 ['sig_sender', [64]],
             ]
         }]]);
@@ -77,7 +88,13 @@ const main = async (channelid, stakeholder_keypair, amount) => {
             fields: [
                 ['encoded_data', ["u8"]],
                 ['prev_state', 'string'],
-                ['sig_oracle', [64]]
+                ['sig_oracles', [OracleSignature]]
+            ]
+        }], [OracleSignature, {
+            kind: 'struct',
+            fields: [
+                ['key_version', 'u32'],
+                ['signature', [64]]
             ]
         }]]);
 
@@ -99,6 +116,8 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 address: stakeholder_keypair.publicKey.toBytes(),
                 balance: amount,
                 sender: stakeholder_keypair.publicKey.toBytes(),
+                sequence: sequence,
+                channel_nonce: channel_nonce,
             }
         );
 
@@ -127,6 +146,8 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 address: stakeholder_keypair.publicKey.toBytes(),
                 balance: amount,
                 sender: stakeholder_keypair.publicKey.toBytes(),
+                sequence: sequence,
+                channel_nonce: channel_nonce,
                 sig_sender: sig_sender_join,
             }
         );
@@ -134,10 +155,11 @@ const main = async (channelid, stakeholder_keypair, amount) => {
         let join_token_core_data_for_sig_verification_encoded = borsh.serialize(join_token_core_data_schema_oracle, join_token_core_data_for_sig_verification);
         
         // verify oracle signature 
+        const sig_oracle = join_token_decoded.sig_oracles[0];
         const verify_oracle_sig = Nacl.sign.detached.verify(
             join_token_core_data_for_sig_verification_encoded,
-            join_token_decoded.sig_oracle,
-            Base58.decode(oracle_public_key));
+            sig_oracle.signature,
+            Base58.decode(oracle_signing_key));
 
         if (!verify_oracle_sig) {
             console.log('Oracle signature is not valid!');
@@ -160,10 +182,19 @@ const main = async (channelid, stakeholder_keypair, amount) => {
         const pda_stakeholder = Web3.PublicKey.findProgramAddressSync([Buffer.from(channelid), stakeholder_keypair.publicKey.toBuffer()], program_pubkey);
         const pda_oracle = Web3.PublicKey.findProgramAddressSync([Buffer.from(Base58.decode(oracle_public_key))], program_pubkey) 
 
+        // program checks both signatures against the Ed25519Program instructions of the transaction
+        const verify_sender_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: stakeholder_keypair.publicKey.toBytes(),
+            message: Uint8Array.from(join_token_core_data_sender_encoded),
+            signature: sig_sender_join,
+        });
+
+        transaction.add(verify_sender_sig_instruction);
+
         const verify_oracle_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
-            publicKey: Base58.decode(oracle_public_key),
+            publicKey: Base58.decode(oracle_signing_key),
             message: Uint8Array.from(join_token_core_data_for_sig_verification_encoded),
-            signature: join_token_decoded.sig_oracle, 
+            signature: sig_oracle.signature, 
         });
 
         transaction.add(verify_oracle_sig_instruction);
@@ -173,7 +204,7 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 {
                     pubkey: stakeholder_keypair.publicKey,
                     isSigner: true,
-                    isWritable: false,
+                    isWritable: true, // pays the join amount
                 },
                 {
                     pubkey: pda_channel[0],
@@ -185,11 +216,6 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                     isSigner: false,
                     isWritable: true,
                 },
-                {
-                    pubkey: pda_oracle[0],
-                    isSigner: false,
-                    isWritable: true,
-                },
                 {
                     pubkey: Web3.SYSVAR_INSTRUCTIONS_PUBKEY,
                     isSigner: false,
@@ -199,6 +225,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                     pubkey: Web3.SystemProgram.programId,
                     isSigner: false,
                     isWritable: false
+                },
+                // oracle PDAs, one per signature in sig_oracles (same order)
+                {
+                    pubkey: pda_oracle[0],
+                    isSigner: false,
+                    isWritable: false,
                 }
             ],
             data: join_instruction_buffer,
@@ -213,6 +245,10 @@ const main = async (channelid, stakeholder_keypair, amount) => {
 
     } catch (error) {
         console.log(error)
+        const program_error = program_errors.decode_program_error(error);
+        if (program_error) {
+            console.log("Program error " + program_error.code + ": " + program_error.name);
+        }
         return Promise.reject(error);
     }
 
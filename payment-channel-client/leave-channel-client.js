@@ -4,6 +4,7 @@ const Nacl = require("tweetnacl");
 const Base58 = require("base-58");
 
 const configuration = require("./configuration.js");
+const program_errors = require("./utils/program-errors-client.js");
 const oracle_channel = require("../payment-channel-service/oracle.js");
 const state_helper = require('../payment-channel-service/utils/state-helper-oracle.js');
 
@@ -15,6 +16,7 @@ const program_pubkey = new Web3.PublicKey(program_address);
 
 // ORACLE DATA
 const oracle_public_key = configuration.oracle_public_key;
+const oracle_signing_key = configuration.oracle_signing_key;
 
 class Assignable {
     constructor(properties) {
@@ -30,6 +32,9 @@ class InstructionPayload extends Assignable {
 class Data extends Assignable {
 }
 
+class OracleSignature extends Assignable {
+}
+
 const main = async (channelid, stakeholder_keypair) => {
 
     console.log("Leaving channel " + channelid + " for " + stakeholder_keypair.publicKey)
@@ -44,6 +49,8 @@ const main = async (channelid, stakeholder_keypair) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
             ]
         }]]);
 
@@ -55,6 +62,8 @@ const main = async (channelid, stakeholder_keypair) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
                 ['sig_sender', [64]],
             ]
         }]]);
@@ -64,7 +73,13 @@ const main = async (channelid, stakeholder_keypair) => {
             fields: [
                 ['encoded_data', ["u8"]],
                 ['prev_state', 'string'],
-                ['sig_oracle', [64]],
+                ['sig_oracles', [OracleSignature]],
+            ]
+        }], [OracleSignature, {
+            kind: 'struct',
+            fields: [
+                ['key_version', 'u32'],
+                ['signature', [64]]
             ]
         }]]);
 
@@ -96,6 +111,8 @@ const main = async (channelid, stakeholder_keypair) => {
             address: pre_leave_data_decoded.address,
             balance: pre_leave_data_decoded.balance,
             sender: pre_leave_data_decoded.sender,
+            sequence: pre_leave_data_decoded.sequence,
+            channel_nonce: pre_leave_data_decoded.channel_nonce,
             sig_sender: leave_data_signed_sender,
         });
 
@@ -111,7 +128,8 @@ const main = async (channelid, stakeholder_keypair) => {
         // const leave_token_core_data_decoded = borsh.deserialize(leave_token_core_data_schema, Data, Buffer.from(leave_token_decoded.encoded_data, 'hex'))
 
         // verify signature
-        const verify_oracle_sig = Nacl.sign.detached.verify(Buffer.from(leave_token_decoded.encoded_data, 'hex'), leave_token_decoded.sig_oracle, Base58.decode(oracle_public_key))
+        const sig_oracle = leave_token_decoded.sig_oracles[0];
+        const verify_oracle_sig = Nacl.sign.detached.verify(Buffer.from(leave_token_decoded.encoded_data, 'hex'), sig_oracle.signature, Base58.decode(oracle_signing_key))
 
         if (!verify_oracle_sig) {
             console.log("Verification of oracle signature failed")
@@ -132,10 +150,18 @@ const main = async (channelid, stakeholder_keypair) => {
         const pda_stakeholder = Web3.PublicKey.findProgramAddressSync([Buffer.from(channelid), stakeholder_keypair.publicKey.toBuffer()], program_pubkey);
         const pda_oracle = Web3.PublicKey.findProgramAddressSync([Buffer.from(Base58.decode(oracle_public_key))], program_pubkey)
 
+        // program checks both signatures against the Ed25519Program instructions of the transaction
+        const verify_sender_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: stakeholder_keypair.publicKey.toBytes(),
+            message: Uint8Array.from(pre_leave_data_encoded),
+            signature: leave_data_signed_sender
+        })
+        transaction.add(verify_sender_sig_instruction);
+
         const verify_oracle_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
-            publicKey: Base58.decode(oracle_public_key),
+            publicKey: Base58.decode(oracle_signing_key),
             message: Uint8Array.from(Buffer.from(leave_token_decoded.encoded_data, 'hex')),
-            signature: leave_token_decoded.sig_oracle
+            signature: sig_oracle.signature
         })
         transaction.add(verify_oracle_sig_instruction);
 
@@ -156,20 +182,16 @@ const main = async (channelid, stakeholder_keypair) => {
                     isSigner: false,
                     isWritable: true,
                 },
-                {
-                    pubkey: pda_oracle[0],
-                    isSigner: false,
-                    isWritable: true,
-                },
                 {
                     pubkey: Web3.SYSVAR_INSTRUCTIONS_PUBKEY,
                     isSigner: false,
                     isWritable: false,
                 },
+                // oracle PDAs, one per signature in sig_oracles (same order)
                 {
-                    pubkey: Web3.SystemProgram.programId,
+                    pubkey: pda_oracle[0],
                     isSigner: false,
-                    isWritable: false
+                    isWritable: false,
                 }
             ],
             data: leave_channel_instruction_buffer,
@@ -184,6 +206,10 @@ const main = async (channelid, stakeholder_keypair) => {
 
     } catch (error) {
         console.log(error)
+        const program_error = program_errors.decode_program_error(error);
+        if (program_error) {
+            console.log("Program error " + program_error.code + ": " + program_error.name);
+        }
         return Promise.reject(error);
     }
 
@@ -195,6 +221,8 @@ const leave_helper = async (channelid, sender_public_key) => {
 
         const action = 4; // off-chain tx
         const sender = sender_public_key.toString();
+        const sequence = await oracle_channel.next_sequence(channelid);
+        const channel_nonce = await oracle_channel.channel_nonce(channelid);
 
         const last_off_chain_state = await state_helper.get_last_channel_state(channelid);
 
@@ -219,6 +247,8 @@ const leave_helper = async (channelid, sender_public_key) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
             ]
         }]]);
 
@@ -228,6 +258,8 @@ const leave_helper = async (channelid, sender_public_key) => {
             address: sender_public_key.toBytes(),
             balance: sender_balance,
             sender: sender_public_key.toBytes(),
+            sequence,
+            channel_nonce,
         });
 
         const pre_leave_token_core_data_encoded = borsh.serialize(pre_leave_token_core_data_schema, pre_leave_token_core_data);
@@ -4,6 +4,7 @@ const Nacl = require("tweetnacl");
 const Base58 = require("base-58");
 
 const configuration = require("./configuration.js");
+const program_errors = require("./utils/program-errors-client.js");
 const oracle_channel = require("../payment-channel-service/oracle.js");
 const connection = configuration.connection;
 
@@ -12,6 +13,7 @@ const program_address = configuration.program_id;
 const program_pubkey = new Web3.PublicKey(program_address);
 
 const oracle_public_key = configuration.oracle_public_key;
+const oracle_signing_key = configuration.oracle_signing_key;
 
 class Assignable {
     constructor(properties) {
@@ -27,6 +29,9 @@ class InstructionPayload extends Assignable {
 class Data extends Assignable {
 }
 
+class OracleSignature extends Assignable {
+}
+
 const main = async (channelid, stakeholder_keypair, amount) => {
 
     console.log("Opening channel " + channelid + " with " + amount + " tokens" + " for " + stakeholder_keypair.publicKey)    
@@ -35,6 +40,9 @@ const main = async (channelid, stakeholder_keypair, amount) => {
         // Static variables
         const prev_state = "0";
         const action = 1;
+        const sequence = await oracle_channel.next_sequence(channelid);
+        // channel id may have been used (opened and closed) before, so the next incarnation
+        const channel_nonce = await oracle_channel.channel_nonce(channelid) + 1;
 
         // Schemas
         const open_token_core_data_schema_sender = new Map([[Data, {
@@ -45,6 +53,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
+                ['mint', [32]],
+                ['oracles', [[32]]],
+                ['oracle_threshold', 'u8'],
+                ['emergency_timeout_slots', 'u64'],
             ]
         }]]);
 
@@ -62,7 +76,13 @@ const main = async (channelid, stakeholder_keypair, amount) => {
             fields: [
                 ['encoded_data', ["u8"]],
                 ['prev_state', 'string'],
-                ['sig_oracle', [64]]
+                ['sig_oracles', [OracleSignature]]
+            ]
+        }], [OracleSignature, {
+            kind: 'struct',
+            fields: [
+                ['key_version', 'u32'],
+                ['signature', [64]]
             ]
         }]]);
 
@@ -74,6 +94,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
+                ['mint', [32]],
+                ['oracles', [[32]]],
+                ['oracle_threshold', 'u8'],
+                ['emergency_timeout_slots', 'u64'],
                 ['sig_sender', [64]],
             ]
         }]]);
@@ -95,6 +121,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 address: stakeholder_keypair.publicKey.toBytes(),
                 balance: amount,
                 sender: stakeholder_keypair.publicKey.toBytes(),
+                sequence: sequence,
+                channel_nonce: channel_nonce,
+                mint: Web3.PublicKey.default.toBytes(), // SOL denominated channel
+                oracles: [Base58.decode(oracle_public_key)],
+                oracle_threshold: 1,
+                emergency_timeout_slots: configuration.emergency_timeout_slots,
             }
         );
 
@@ -125,6 +157,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 address: stakeholder_keypair.publicKey.toBytes(),
                 balance: amount,
                 sender: stakeholder_keypair.publicKey.toBytes(),
+                sequence: sequence,
+                channel_nonce: channel_nonce,
+                mint: Web3.PublicKey.default.toBytes(), // SOL denominated channel
+                oracles: [Base58.decode(oracle_public_key)],
+                oracle_threshold: 1,
+                emergency_timeout_slots: configuration.emergency_timeout_slots,
                 sig_sender: sig_sender_open
             }
         );
@@ -133,10 +171,11 @@ const main = async (channelid, stakeholder_keypair, amount) => {
         const open_token_core_data_encoded_for_sig_verification = borsh.serialize(open_token_core_data_schema_oracle, open_token_core_data_for_sig_verification); // buffer is returned
 
         // verify oracle signature 
+        const sig_oracle = open_token_decoded.sig_oracles[0];
         const verify_oracle_sig = Nacl.sign.detached.verify(
             open_token_core_data_encoded_for_sig_verification,
-            open_token_decoded.sig_oracle,
-            Base58.decode(oracle_public_key));
+            sig_oracle.signature,
+            Base58.decode(oracle_signing_key));
 
         if (!verify_oracle_sig) {
             console.log('Oracle signature is not valid!');
@@ -156,11 +195,20 @@ const main = async (channelid, stakeholder_keypair, amount) => {
         const pda_channel = Web3.PublicKey.findProgramAddressSync([Buffer.from(channelid)], program_pubkey);
         const pda_stakeholder = Web3.PublicKey.findProgramAddressSync([Buffer.from(channelid), stakeholder_keypair.publicKey.toBuffer()], program_pubkey);
         const pda_oracle = Web3.PublicKey.findProgramAddressSync([Buffer.from(Base58.decode(oracle_public_key))], program_pubkey) 
+        const pda_nonce = Web3.PublicKey.findProgramAddressSync([Buffer.from("nonce"), Buffer.from(channelid)], program_pubkey);
+
+        // program checks both signatures against the Ed25519Program instructions of the transaction
+        const verify_sender_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
+            publicKey: stakeholder_keypair.publicKey.toBytes(),
+            message: Uint8Array.from(open_token_core_data_encoded),
+            signature: sig_sender_open,
+        })
+        transaction.add(verify_sender_sig_instruction);
 
         const verify_oracle_sig_instruction = Web3.Ed25519Program.createInstructionWithPublicKey({
-            publicKey: Base58.decode(oracle_public_key), 
+            publicKey: Base58.decode(oracle_signing_key), 
             message: Uint8Array.from(open_token_core_data_encoded_for_sig_verification),
-            signature: open_token_decoded.sig_oracle,
+            signature: sig_oracle.signature,
         })
         transaction.add(verify_oracle_sig_instruction);
 
@@ -169,7 +217,7 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                 {
                     pubkey: stakeholder_keypair.publicKey,
                     isSigner: true,
-                    isWritable: false,
+                    isWritable: true, // pays the PDA rent and the open amount
                 },
                 {
                     pubkey: pda_channel[0],
@@ -182,7 +230,7 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                     isWritable: true,
                 },
                 {
-                    pubkey: pda_oracle[0],
+                    pubkey: pda_nonce[0], // created by the first open of the channel id
                     isSigner: false,
                     isWritable: true,
                 },
@@ -195,6 +243,12 @@ const main = async (channelid, stakeholder_keypair, amount) => {
                     pubkey: Web3.SystemProgram.programId,
                     isSigner: false,
                     isWritable: false
+                },
+                // oracle PDAs, one per signature in sig_oracles (same order)
+                {
+                    pubkey: pda_oracle[0],
+                    isSigner: false,
+                    isWritable: false,
                 }
             ],
             data: open_channel_instruction_buffer,
@@ -209,6 +263,10 @@ const main = async (channelid, stakeholder_keypair, amount) => {
 
     } catch (error) {
         console.log(error)
+        const program_error = program_errors.decode_program_error(error);
+        if (program_error) {
+            console.log("Program error " + program_error.code + ": " + program_error.name);
+        }
         return Promise.reject(error);
     }
 
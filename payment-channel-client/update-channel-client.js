@@ -44,6 +44,8 @@ const main = async (channelid, sender_keypair, amount, receiver_public_key) => {
             fields.push([`balance${i}`, 'u64']);
         }
         fields.push(['sender', [32]]);
+        fields.push(['sequence', 'u64']);
+        fields.push(['channel_nonce', 'u64']);
         fields.push(['num_of_stakeholders', 'u8']);
 
         const off_chain_token_data_schema = new Map([[Data, {
@@ -92,6 +94,8 @@ const update_helper = async (channelid, sender_public_key, amount, receiver_publ
 
         const action = 3; // off-chain tx
         const sender = sender_public_key.toString();
+        const sequence = await oracle_channel.next_sequence(channelid);
+        const channel_nonce = await oracle_channel.channel_nonce(channelid);
         const receiver = receiver_public_key.toString();
 
         const last_off_chain_state = await state_helper.get_last_channel_state(channelid);
@@ -140,6 +144,8 @@ const update_helper = async (channelid, sender_public_key, amount, receiver_publ
             fields.push([`balance${i}`, 'u64']);
         }
         fields.push(['sender', [32]]);
+        fields.push(['sequence', 'u64']);
+        fields.push(['channel_nonce', 'u64']);
         fields.push(['num_of_stakeholders', 'u8']);
 
         const off_chain_token_data_schema = new Map([[Data, {
@@ -158,6 +164,8 @@ const update_helper = async (channelid, sender_public_key, amount, receiver_publ
             pre_off_chain_tx_data["balance" + (index + 1)] = item.balance;
         });
         pre_off_chain_tx_data.sender = sender_public_key.toBytes();
+        pre_off_chain_tx_data.sequence = sequence;
+        pre_off_chain_tx_data.channel_nonce = channel_nonce;
         pre_off_chain_tx_data.num_of_stakeholders = num_of_stakeholders;
 
         const pre_off_chain_tx_oracle_encoded = borsh.serialize(off_chain_token_data_schema, pre_off_chain_tx_data);
@@ -0,0 +1,63 @@
+// Error codes of the payment channel program (see payment-channel-smart-contracts/error.rs)
+// Failed transactions report them as "custom program error: 0x<code>"
+const program_errors = [
+    'Error',
+    'InvalidSeeds',
+    'InvalidTokenEncoding',
+    'AccountNotInitialized',
+    'AccountAlreadyInitialized',
+    'WrongAction',
+    'WrongPrevState',
+    'ChannelIdMismatch',
+    'ChannelNotOpened',
+    'InvalidStakeholderStatus',
+    'SenderMismatch',
+    'InvalidAmount',
+    'StaleSequence',
+    'InvalidSenderSignature',
+    'InvalidOracleSignature',
+    'NotEnoughOracleSignatures',
+    'InvalidOracleSet',
+    'OracleNotInChannel',
+    'OracleInactive',
+    'OracleKeyNotAccepted',
+    'InsufficientFunds',
+    'ArithmeticOverflow',
+    'Unauthorized',
+    'InvalidOwner',
+    'DisputeWindowOpen',
+    'DisputeWindowClosed',
+    'EmergencyExitUnavailable',
+    'InvalidTokenAccount',
+    'InvalidEd25519Instruction',
+    'SignatureNotFound',
+    'InvalidFraudProof',
+    'BondAlreadySlashed',
+    'CheckpointMismatch',
+    'InvalidMerkleProof',
+    'ProofAlreadyClaimed',
+    'StateMismatch',
+    'InvalidArgument',
+    'UnknownInstruction',
+    'InstructionTooShort',
+    'InstructionLengthMismatch',
+    'InvalidInstructionPayload',
+    'VaultNotEmpty',
+    'ChannelNonceMismatch',
+    'ReservedChannelId',
+];
+
+function decode_program_error(error) {
+    const match = /custom program error: (0x[0-9a-fA-F]+)/.exec(String(error && error.message ? error.message : error));
+    if (!match) {
+        return undefined;
+    }
+
+    const code = parseInt(match[1], 16);
+    return { code, name: program_errors[code] || 'Unknown' };
+}
+
+module.exports = {
+    program_errors,
+    decode_program_error
+}
@@ -0,0 +1,68 @@
+use solana_program::{
+    ed25519_program, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey,
+};
+
+use payment_channel_smart_contracts::error::PaymentChannelError;
+
+// 2*u8 (num_signatures + padding)
+const SIGNATURE_OFFSETS_START: usize = 2;
+// 7*u16 (see Ed25519SignatureOffsets in solana-sdk)
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+// Offsets point into the Ed25519Program instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+// Signature to be checked by the Ed25519Program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ed25519Signature {
+    pub pubkey: Pubkey,
+    pub message: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+// Single Ed25519Program instruction carrying every signature; the program looks the signatures
+// up within the transaction (see verify_signature_processor), so one instruction per token is enough.
+// Offsets are u16 and the count a u8; signatures or messages beyond that can not be carried.
+pub fn ed25519_instruction(signatures: &[Ed25519Signature]) -> Result<Instruction, ProgramError> {
+    let num_signatures = u8::try_from(signatures.len()).map_err(|_| too_large())?;
+
+    let mut data =
+        vec![0u8; SIGNATURE_OFFSETS_START + signatures.len() * SIGNATURE_OFFSETS_SERIALIZED_SIZE];
+    data[0] = num_signatures; // Byte 0 (Byte 1 = padding)
+
+    for (i, entry) in signatures.iter().enumerate() {
+        // pubkey, signature and message are appended after the offsets
+        let public_key_offset = u16::try_from(data.len()).map_err(|_| too_large())?;
+        data.extend_from_slice(entry.pubkey.as_ref());
+
+        let signature_offset = u16::try_from(data.len()).map_err(|_| too_large())?;
+        data.extend_from_slice(&entry.signature);
+
+        let message_data_offset = u16::try_from(data.len()).map_err(|_| too_large())?;
+        data.extend_from_slice(&entry.message);
+
+        let offsets = [
+            signature_offset,
+            CURRENT_INSTRUCTION,
+            public_key_offset,
+            CURRENT_INSTRUCTION,
+            message_data_offset,
+            u16::try_from(entry.message.len()).map_err(|_| too_large())?,
+            CURRENT_INSTRUCTION,
+        ];
+
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        for (j, offset) in offsets.iter().enumerate() {
+            data[start + 2 * j..start + 2 * j + 2].copy_from_slice(&offset.to_le_bytes());
+        }
+    }
+
+    Ok(Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    })
+}
+
+fn too_large() -> ProgramError {
+    PaymentChannelError::InvalidEd25519Instruction.into()
+}
@@ -0,0 +1,252 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use payment_channel_smart_contracts::error::PaymentChannelError;
+use payment_channel_smart_contracts::instruction::ChannelInstruction;
+use payment_channel_token::TokenPayload;
+
+use crate::ed25519::{ed25519_instruction, Ed25519Signature};
+use crate::pda::{
+    bond_pda, channel_pda, config_pda, nonce_pda, oracle_pda, stakeholder_pda, vault_pda,
+};
+
+// Oracle behind `sig_oracles[i]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OracleSigner {
+    pub oracle: Pubkey,      // identity (registry entry)
+    pub signing_key: Pubkey, // key of `key_version` (differs from `oracle` once the key was rotated)
+}
+
+// Only for SPL Token denominated channels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenAccounts {
+    pub mint: Pubkey,
+    pub sender_token_account: Pubkey,
+}
+
+// Registry entry written by ModifyOracle
+#[derive(Debug, Clone, PartialEq)]
+pub struct OracleEntry {
+    pub oracle_address: Pubkey,
+    pub oracle_status: bool,
+    pub name: String,
+    pub endpoint_hash: [u8; 32],
+    pub fee: u64,
+}
+
+// Ed25519Program instruction with the sender signature and every oracle signature of `token`
+pub fn token_ed25519_instruction(
+    sender: &Pubkey,
+    token: &TokenPayload,
+    oracles: &[OracleSigner],
+) -> Result<Instruction, ProgramError> {
+    // One OracleSigner per oracle signature
+    if oracles.len() != token.sig_oracles.len() {
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
+
+    // Token core data ends with sig_sender
+    let sig_sender = token
+        .sig_sender()
+        .ok_or(PaymentChannelError::InvalidTokenEncoding)?;
+
+    let mut signatures = vec![Ed25519Signature {
+        pubkey: *sender,
+        message: token.sender_preimage().to_vec(),
+        signature: sig_sender,
+    }];
+
+    signatures.extend(
+        oracles
+            .iter()
+            .zip(&token.sig_oracles)
+            .map(|(signer, sig_oracle)| Ed25519Signature {
+                pubkey: signer.signing_key,
+                message: token.oracle_preimage().to_vec(),
+                signature: sig_oracle.signature,
+            }),
+    );
+
+    ed25519_instruction(&signatures)
+}
+
+fn oracle_accounts(oracles: &[OracleSigner]) -> Vec<AccountMeta> {
+    oracles
+        .iter()
+        .map(|signer| AccountMeta::new_readonly(oracle_pda(&signer.oracle), false))
+        .collect()
+}
+
+// [Ed25519Program instruction, OpenChannel]
+pub fn open_channel(
+    sender: &Pubkey,
+    channel_id: &str,
+    token: &TokenPayload,
+    oracles: &[OracleSigner],
+    token_accounts: Option<&TokenAccounts>,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(*sender, true),
+        AccountMeta::new(channel_pda(channel_id), false),
+        AccountMeta::new(stakeholder_pda(channel_id, sender), false),
+        AccountMeta::new(nonce_pda(channel_id), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(oracle_accounts(oracles));
+
+    if let Some(token_accounts) = token_accounts {
+        accounts.extend([
+            AccountMeta::new_readonly(token_accounts.mint, false),
+            AccountMeta::new(vault_pda(channel_id), false),
+            AccountMeta::new(token_accounts.sender_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+
+    let instruction = ChannelInstruction::OpenChannel {
+        open_token_encoded: token.try_to_vec()?,
+    };
+
+    Ok(vec![
+        token_ed25519_instruction(sender, token, oracles)?,
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: instruction.pack()?,
+        },
+    ])
+}
+
+// [Ed25519Program instruction, JoinChannel]; `sender` must have been invited
+pub fn join_channel(
+    sender: &Pubkey,
+    channel_id: &str,
+    token: &TokenPayload,
+    oracles: &[OracleSigner],
+    token_accounts: Option<&TokenAccounts>,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(*sender, true),
+        AccountMeta::new(channel_pda(channel_id), false),
+        AccountMeta::new(stakeholder_pda(channel_id, sender), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(oracle_accounts(oracles));
+
+    if let Some(token_accounts) = token_accounts {
+        accounts.extend([
+            AccountMeta::new(vault_pda(channel_id), false),
+            AccountMeta::new(token_accounts.sender_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+
+    let instruction = ChannelInstruction::JoinChannel {
+        join_token_encoded: token.try_to_vec()?,
+    };
+
+    Ok(vec![
+        token_ed25519_instruction(sender, token, oracles)?,
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: instruction.pack()?,
+        },
+    ])
+}
+
+// [Ed25519Program instruction, LeaveChannel]; funds are released by FinalizeLeave
+pub fn leave_channel(
+    sender: &Pubkey,
+    channel_id: &str,
+    token: &TokenPayload,
+    oracles: &[OracleSigner],
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(*sender, true),
+        AccountMeta::new(channel_pda(channel_id), false),
+        AccountMeta::new(stakeholder_pda(channel_id, sender), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    accounts.extend(oracle_accounts(oracles));
+
+    let instruction = ChannelInstruction::LeaveChannel {
+        leave_token_encoded: token.try_to_vec()?,
+    };
+
+    Ok(vec![
+        token_ed25519_instruction(sender, token, oracles)?,
+        Instruction {
+            program_id: crate::ID,
+            accounts,
+            data: instruction.pack()?,
+        },
+    ])
+}
+
+// InviteToChannel; `sender` is an active stakeholder and pays the rent of the invitation
+pub fn invite_to_channel(
+    sender: &Pubkey,
+    channel_id: &str,
+    invitee: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let instruction = ChannelInstruction::InviteToChannel {
+        channel_id: channel_id.to_string(),
+        invitee: *invitee,
+    };
+
+    Ok(Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(*sender, true),
+            AccountMeta::new_readonly(channel_pda(channel_id), false),
+            AccountMeta::new_readonly(stakeholder_pda(channel_id, sender), false),
+            AccountMeta::new(stakeholder_pda(channel_id, invitee), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction.pack()?,
+    })
+}
+
+// ModifyOracle (admin only); `register` = first registration of the oracle, which has to
+// co-sign the transaction as it posts the bond, or reactivation of a suspended one (bond checked)
+pub fn modify_oracle(
+    admin: &Pubkey,
+    entry: &OracleEntry,
+    register: bool,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(oracle_pda(&entry.oracle_address), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(config_pda(), false),
+    ];
+
+    if register {
+        accounts.extend([
+            AccountMeta::new(entry.oracle_address, true),
+            AccountMeta::new(bond_pda(&entry.oracle_address), false),
+        ]);
+    }
+
+    let instruction = ChannelInstruction::ModifyOracle {
+        oracle_address: entry.oracle_address,
+        oracle_status: entry.oracle_status,
+        name: entry.name.clone(),
+        endpoint_hash: entry.endpoint_hash,
+        fee: entry.fee,
+    };
+
+    Ok(Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: instruction.pack()?,
+    })
+}
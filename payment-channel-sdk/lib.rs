@@ -0,0 +1,8 @@
+// Client side helpers for the payment channel program: PDA derivation and instruction builders.
+// Depends on the program built with the `no-entrypoint` feature (instruction layout, seeds).
+// Accounts are derived for, and instructions addressed to, the program ID declared by the program.
+pub mod ed25519;
+pub mod instructions;
+pub mod pda;
+
+pub use payment_channel_smart_contracts::ID;
@@ -0,0 +1,43 @@
+use solana_program::pubkey::Pubkey;
+
+use payment_channel_smart_contracts::bond_state::BOND_SEED;
+use payment_channel_smart_contracts::config_state::CONFIG_SEED;
+use payment_channel_smart_contracts::nonce_state::NONCE_SEED;
+use payment_channel_smart_contracts::token_vault::VAULT_SEED;
+
+// channel_pda = (channelid)
+pub fn channel_pda(channel_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[channel_id.as_bytes()], &crate::ID).0
+}
+
+// stakeholder_pda = (channelid+stakeholder); also the pending invitation of an invitee
+pub fn stakeholder_pda(channel_id: &str, stakeholder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[channel_id.as_bytes(), stakeholder.as_ref()], &crate::ID).0
+}
+
+// nonce_pda = ("nonce"+channelid); outlives the channel PDA
+pub fn nonce_pda(channel_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[NONCE_SEED, channel_id.as_bytes()], &crate::ID).0
+}
+
+// oracle_pda = (oracle_address); registry entry of the oracle
+pub fn oracle_pda(oracle: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[oracle.as_ref()], &crate::ID).0
+}
+
+// vault_pda = ("vault"+channel_pda); token account of SPL Token denominated channels
+pub fn vault_pda(channel_id: &str) -> Pubkey {
+    let pda_channel = channel_pda(channel_id);
+
+    Pubkey::find_program_address(&[VAULT_SEED, pda_channel.as_ref()], &crate::ID).0
+}
+
+// config_pda = ("config")
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], &crate::ID).0
+}
+
+// bond_pda = ("bond"+oracle_address)
+pub fn bond_pda(oracle: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BOND_SEED, oracle.as_ref()], &crate::ID).0
+}
@@ -0,0 +1,370 @@
+// Builders against the program: accounts in the order the processors read them, instruction
+// data that ChannelInstruction::unpack (lib.rs) dispatches to the right processor, and an
+// Ed25519Program instruction the program parses back into the token signatures.
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use payment_channel_sdk::ed25519::{ed25519_instruction, Ed25519Signature};
+use payment_channel_sdk::instructions::{
+    invite_to_channel, join_channel, leave_channel, modify_oracle, open_channel,
+    token_ed25519_instruction, OracleEntry, OracleSigner, TokenAccounts,
+};
+use payment_channel_sdk::pda::{
+    bond_pda, channel_pda, config_pda, nonce_pda, oracle_pda, stakeholder_pda, vault_pda,
+};
+use payment_channel_sdk::ID;
+use payment_channel_smart_contracts::bond_state::BOND_SEED;
+use payment_channel_smart_contracts::config_state::CONFIG_SEED;
+use payment_channel_smart_contracts::error::PaymentChannelError;
+use payment_channel_smart_contracts::instruction::ChannelInstruction;
+use payment_channel_smart_contracts::nonce_state::NONCE_SEED;
+use payment_channel_smart_contracts::token_vault::VAULT_SEED;
+use payment_channel_smart_contracts::verify_signature_processor::parse_ed25519_instruction;
+use payment_channel_token::{
+    BalanceTokenData, OracleSignature, TokenPayload, ACTION_JOIN, SIG_SENDER_LEN,
+};
+
+const CHANNEL_ID: &str = "channel";
+
+fn oracle_signers() -> Vec<OracleSigner> {
+    vec![
+        OracleSigner {
+            oracle: Pubkey::new_unique(),
+            signing_key: Pubkey::new_unique(),
+        },
+        OracleSigner {
+            oracle: Pubkey::new_unique(),
+            signing_key: Pubkey::new_unique(),
+        },
+    ]
+}
+
+fn token(sender: &Pubkey) -> TokenPayload {
+    let core_data = BalanceTokenData {
+        action: ACTION_JOIN,
+        channelid: CHANNEL_ID.to_string(),
+        address: *sender,
+        balance: 100,
+        sender: *sender,
+        sequence: 2,
+        channel_nonce: 1,
+        sig_sender: [9; 64],
+    };
+
+    TokenPayload {
+        encoded_data: core_data.try_to_vec().unwrap(),
+        prev_state: "0".to_string(),
+        sig_oracles: vec![
+            OracleSignature {
+                key_version: 0,
+                signature: [7; 64],
+            },
+            OracleSignature {
+                key_version: 1,
+                signature: [8; 64],
+            },
+        ],
+    }
+}
+
+fn oracle_metas(oracles: &[OracleSigner]) -> Vec<AccountMeta> {
+    oracles
+        .iter()
+        .map(|signer| AccountMeta::new_readonly(oracle_pda(&signer.oracle), false))
+        .collect()
+}
+
+// Ed25519Program instruction as the program reads it: sender signature first, then one per oracle
+fn check_ed25519_instruction(
+    instruction: &Instruction,
+    sender: &Pubkey,
+    token: &TokenPayload,
+    oracles: &[OracleSigner],
+) {
+    assert_eq!(instruction.program_id, ed25519_program::id());
+
+    let (key, owner) = (sysvar::instructions::id(), Pubkey::default());
+    let (mut lamports, mut data) = (0, vec![]);
+    let sysvar_account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let entries = parse_ed25519_instruction(&sysvar_account, 0, &instruction.data).unwrap();
+    assert_eq!(entries.len(), 1 + oracles.len());
+
+    assert_eq!(entries[0].pubkey, *sender);
+    assert_eq!(entries[0].message, token.sender_preimage());
+    assert_eq!(Some(entries[0].signature), token.sig_sender());
+
+    for ((entry, signer), sig_oracle) in entries[1..].iter().zip(oracles).zip(&token.sig_oracles) {
+        assert_eq!(entry.pubkey, signer.signing_key);
+        assert_eq!(entry.message, token.oracle_preimage());
+        assert_eq!(entry.signature, sig_oracle.signature);
+    }
+}
+
+fn check_program_instruction(
+    instruction: &Instruction,
+    accounts: Vec<AccountMeta>,
+    data: ChannelInstruction,
+) {
+    assert_eq!(instruction.program_id, ID);
+    assert_eq!(instruction.accounts, accounts);
+    assert_eq!(ChannelInstruction::unpack(&instruction.data), Ok(data));
+}
+
+#[test]
+fn pda_helpers_use_program_seeds() {
+    let (stakeholder, oracle) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &ID).0;
+
+    let pda_channel = pda(&[CHANNEL_ID.as_bytes()]);
+    assert_eq!(channel_pda(CHANNEL_ID), pda_channel);
+    assert_eq!(
+        stakeholder_pda(CHANNEL_ID, &stakeholder),
+        pda(&[CHANNEL_ID.as_bytes(), stakeholder.as_ref()])
+    );
+    assert_eq!(
+        nonce_pda(CHANNEL_ID),
+        pda(&[NONCE_SEED, CHANNEL_ID.as_bytes()])
+    );
+    assert_eq!(oracle_pda(&oracle), pda(&[oracle.as_ref()]));
+    assert_eq!(
+        vault_pda(CHANNEL_ID),
+        pda(&[VAULT_SEED, pda_channel.as_ref()])
+    );
+    assert_eq!(config_pda(), pda(&[CONFIG_SEED]));
+    assert_eq!(bond_pda(&oracle), pda(&[BOND_SEED, oracle.as_ref()]));
+}
+
+#[test]
+fn open_channel_accounts_and_data() {
+    let (sender, oracles) = (Pubkey::new_unique(), oracle_signers());
+    let token = token(&sender);
+
+    let mut accounts = vec![
+        AccountMeta::new(sender, true),
+        AccountMeta::new(channel_pda(CHANNEL_ID), false),
+        AccountMeta::new(stakeholder_pda(CHANNEL_ID, &sender), false),
+        AccountMeta::new(nonce_pda(CHANNEL_ID), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(oracle_metas(&oracles));
+
+    let instructions = open_channel(&sender, CHANNEL_ID, &token, &oracles, None).unwrap();
+    assert_eq!(instructions.len(), 2);
+    check_ed25519_instruction(&instructions[0], &sender, &token, &oracles);
+    check_program_instruction(
+        &instructions[1],
+        accounts.clone(),
+        ChannelInstruction::OpenChannel {
+            open_token_encoded: token.try_to_vec().unwrap(),
+        },
+    );
+
+    // SPL Token channel: mint, vault, sender token account and token program follow
+    let token_accounts = TokenAccounts {
+        mint: Pubkey::new_unique(),
+        sender_token_account: Pubkey::new_unique(),
+    };
+    accounts.extend([
+        AccountMeta::new_readonly(token_accounts.mint, false),
+        AccountMeta::new(vault_pda(CHANNEL_ID), false),
+        AccountMeta::new(token_accounts.sender_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+
+    let instructions =
+        open_channel(&sender, CHANNEL_ID, &token, &oracles, Some(&token_accounts)).unwrap();
+    assert_eq!(instructions[1].accounts, accounts);
+}
+
+#[test]
+fn join_channel_accounts_and_data() {
+    let (sender, oracles) = (Pubkey::new_unique(), oracle_signers());
+    let token = token(&sender);
+
+    let mut accounts = vec![
+        AccountMeta::new(sender, true),
+        AccountMeta::new(channel_pda(CHANNEL_ID), false),
+        AccountMeta::new(stakeholder_pda(CHANNEL_ID, &sender), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(oracle_metas(&oracles));
+
+    let instructions = join_channel(&sender, CHANNEL_ID, &token, &oracles, None).unwrap();
+    assert_eq!(instructions.len(), 2);
+    check_ed25519_instruction(&instructions[0], &sender, &token, &oracles);
+    check_program_instruction(
+        &instructions[1],
+        accounts.clone(),
+        ChannelInstruction::JoinChannel {
+            join_token_encoded: token.try_to_vec().unwrap(),
+        },
+    );
+
+    // SPL Token channel: vault, sender token account and token program follow (mint is known)
+    let token_accounts = TokenAccounts {
+        mint: Pubkey::new_unique(),
+        sender_token_account: Pubkey::new_unique(),
+    };
+    accounts.extend([
+        AccountMeta::new(vault_pda(CHANNEL_ID), false),
+        AccountMeta::new(token_accounts.sender_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+
+    let instructions =
+        join_channel(&sender, CHANNEL_ID, &token, &oracles, Some(&token_accounts)).unwrap();
+    assert_eq!(instructions[1].accounts, accounts);
+}
+
+#[test]
+fn leave_channel_accounts_and_data() {
+    let (sender, oracles) = (Pubkey::new_unique(), oracle_signers());
+    let token = token(&sender);
+
+    let mut accounts = vec![
+        AccountMeta::new(sender, true),
+        AccountMeta::new(channel_pda(CHANNEL_ID), false),
+        AccountMeta::new(stakeholder_pda(CHANNEL_ID, &sender), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    accounts.extend(oracle_metas(&oracles));
+
+    let instructions = leave_channel(&sender, CHANNEL_ID, &token, &oracles).unwrap();
+    assert_eq!(instructions.len(), 2);
+    check_ed25519_instruction(&instructions[0], &sender, &token, &oracles);
+    check_program_instruction(
+        &instructions[1],
+        accounts,
+        ChannelInstruction::LeaveChannel {
+            leave_token_encoded: token.try_to_vec().unwrap(),
+        },
+    );
+}
+
+#[test]
+fn invite_to_channel_accounts_and_data() {
+    let (sender, invitee) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    check_program_instruction(
+        &invite_to_channel(&sender, CHANNEL_ID, &invitee).unwrap(),
+        vec![
+            AccountMeta::new(sender, true),
+            AccountMeta::new_readonly(channel_pda(CHANNEL_ID), false),
+            AccountMeta::new_readonly(stakeholder_pda(CHANNEL_ID, &sender), false),
+            AccountMeta::new(stakeholder_pda(CHANNEL_ID, &invitee), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        ChannelInstruction::InviteToChannel {
+            channel_id: CHANNEL_ID.to_string(),
+            invitee,
+        },
+    );
+}
+
+#[test]
+fn modify_oracle_accounts_and_data() {
+    let admin = Pubkey::new_unique();
+    let entry = OracleEntry {
+        oracle_address: Pubkey::new_unique(),
+        oracle_status: true,
+        name: "oracle".to_string(),
+        endpoint_hash: [3; 32],
+        fee: 5,
+    };
+    let data = ChannelInstruction::ModifyOracle {
+        oracle_address: entry.oracle_address,
+        oracle_status: entry.oracle_status,
+        name: entry.name.clone(),
+        endpoint_hash: entry.endpoint_hash,
+        fee: entry.fee,
+    };
+
+    let mut accounts = vec![
+        AccountMeta::new(admin, true),
+        AccountMeta::new(oracle_pda(&entry.oracle_address), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(config_pda(), false),
+    ];
+
+    check_program_instruction(
+        &modify_oracle(&admin, &entry, false).unwrap(),
+        accounts.clone(),
+        data.clone(),
+    );
+
+    // Registration: the oracle co-signs and posts the bond
+    accounts.extend([
+        AccountMeta::new(entry.oracle_address, true),
+        AccountMeta::new(bond_pda(&entry.oracle_address), false),
+    ]);
+
+    check_program_instruction(
+        &modify_oracle(&admin, &entry, true).unwrap(),
+        accounts,
+        data,
+    );
+}
+
+#[test]
+fn oversized_signatures_are_rejected() {
+    let invalid_ed25519: ProgramError = PaymentChannelError::InvalidEd25519Instruction.into();
+    let signature = Ed25519Signature {
+        pubkey: Pubkey::new_unique(),
+        message: vec![1; 32],
+        signature: [7; 64],
+    };
+
+    // Message length and offsets are u16
+    let long_message = Ed25519Signature {
+        message: vec![1; u16::MAX as usize + 1],
+        ..signature.clone()
+    };
+    assert_eq!(
+        ed25519_instruction(&[long_message]),
+        Err(invalid_ed25519.clone())
+    );
+
+    // Number of signatures is a u8
+    assert_eq!(
+        ed25519_instruction(&vec![signature.clone(); 256]),
+        Err(invalid_ed25519)
+    );
+    assert!(ed25519_instruction(&vec![signature; 255]).is_ok());
+}
+
+#[test]
+fn token_signatures_have_to_match_oracles() {
+    let (sender, oracles) = (Pubkey::new_unique(), oracle_signers());
+
+    assert_eq!(
+        token_ed25519_instruction(&sender, &token(&sender), &oracles[..1]),
+        Err(PaymentChannelError::InvalidArgument.into())
+    );
+
+    let mut short_token = token(&sender);
+    short_token.encoded_data.truncate(SIG_SENDER_LEN - 1);
+    assert_eq!(
+        token_ed25519_instruction(&sender, &short_token, &oracles),
+        Err(PaymentChannelError::InvalidTokenEncoding.into())
+    );
+}
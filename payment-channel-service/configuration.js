@@ -8,17 +8,28 @@ const oracle_private_key = // insert oracle private key here
 const connection = new Web3.Connection(Web3.clusterApiUrl(network_name), 'confirmed')
 const oracle_public_key = Web3.Keypair.fromSecretKey(Uint8Array.from(oracle_private_key)).publicKey.toBase58()
 
+// Identity of the oracle (registry entry, oracle_pda) and version of the key in oracle_private_key;
+// after RotateOracleKey the oracle signs with a new key but keeps its identity
+const oracle_address = oracle_public_key;
+const oracle_key_version = 0;
+
 // IPFS connection parameters
 const host = "insert IPFS host here";
 const port = 'insert IPFS port here';
 const protocol = "insert IPFS protocol here";
 
+// File the oracle persists the per-channel token sequence to
+const sequence_store_path = __dirname + "/sequence-store.json";
+
 module.exports = {
     program_id,
     oracle_private_key,
     oracle_public_key,
+    oracle_address,
+    oracle_key_version,
     connection,
     host,
     port,
-    protocol
+    protocol,
+    sequence_store_path
 }
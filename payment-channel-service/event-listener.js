@@ -21,6 +21,9 @@ class Assignable {
 class Data extends Assignable {
 }
 
+class OracleSignature extends Assignable {
+}
+
 const logsSubcribe = async () => {
 
     const join_token_core_data_schema_oracle = new Map([[Data, {
@@ -31,16 +34,40 @@ const logsSubcribe = async () => {
             ['address', [32]],
             ['balance', 'u64'],
             ['sender', [32]],
+            ['sequence', 'u64'],
+            ['channel_nonce', 'u64'],
             ['sig_sender', [64]],
         ]
     }]]);
 
+    // ChannelEvent::StakeholderJoined (see events.rs), emitted as "Program data: <base64>"
+    const EVENT_VERSION = 1;
+    const STAKEHOLDER_JOINED = 2;
+
+    const stakeholder_joined_event_schema = new Map([[Data, {
+        kind: 'struct',
+        fields: [
+            ['channel_id', 'string'],
+            ['stakeholder', [32]],
+            ['balance', 'u64'],
+            ['sequence', 'u64'],
+            ['join_token', ["u8"]],
+        ]
+    }]]);
+
+    // Join token as submitted (M-of-N oracle signatures)
     const join_token_schema = new Map([[Data, {
         kind: 'struct',
         fields: [
             ['encoded_data', ["u8"]],
             ['prev_state', 'string'],
-            ['sig_oracle', [64]]
+            ['sig_oracles', [OracleSignature]]
+        ]
+    }], [OracleSignature, {
+        kind: 'struct',
+        fields: [
+            ['key_version', 'u32'],
+            ['signature', [64]]
         ]
     }]]);
 
@@ -48,14 +75,18 @@ const logsSubcribe = async () => {
 
         const subcriptionId = connection.onLogs(logsFilter, (logs, context) => {
             logs.logs.forEach(async log => {
-                if (log.includes('JoinEvent')) {
-                    console.log(log)
-                    const parts = log.split('JoinEvent: ');
-                    const data = parts[1];
-                    console.log(data);
-                    const array = JSON.parse(data)
+                if (log.startsWith('Program data: ')) {
+                    const data = Buffer.from(log.slice('Program data: '.length).split(' ')[0], 'base64');
+
+                    // [version, variant, ...borsh encoded fields]
+                    if (data.length < 2 || data[0] !== EVENT_VERSION || data[1] !== STAKEHOLDER_JOINED) {
+                        return;
+                    }
+
+                    const event = borsh.deserialize(stakeholder_joined_event_schema, Data, data.subarray(2));
+                    console.log("StakeholderJoined: " + event.channel_id);
 
-                    const join_token_decoded = borsh.deserialize(join_token_schema, Data, Buffer.from(Uint8Array.from(array)));
+                    const join_token_decoded = borsh.deserialize(join_token_schema, Data, Buffer.from(event.join_token));
 
                     const join_token_core_data_decoded = borsh.deserialize(join_token_core_data_schema_oracle, Data, Buffer.from(join_token_decoded.encoded_data));
 
@@ -65,7 +96,7 @@ const logsSubcribe = async () => {
                         {
                             encoded_data: join_token_decoded.encoded_data,
                             prev_state: prev_state_cid,
-                            sig_oracle: join_token_decoded.sig_oracle
+                            sig_oracles: join_token_decoded.sig_oracles
                         }
                     );
 
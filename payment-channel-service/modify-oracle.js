@@ -11,7 +11,8 @@ const program_pubkey = new Web3.PublicKey(program_address);
 
 const oracle_keypair = Web3.Keypair.fromSecretKey(Uint8Array.from(configuration.oracle_private_key));
 
-const main = async () => {
+// Registers (bond is posted by the oracle) or updates the oracle; must be signed by the program admin
+const main = async (admin_keypair, oracle_status = true, name = "", endpoint_hash = new Uint8Array(32), fee = 0) => {
 
     class Assignable {
         constructor(properties) {
@@ -28,12 +29,20 @@ const main = async () => {
         kind: 'struct',
         fields: [
             ['variant', 'u8'],
-            ['oracle_address', [32]]]
+            ['oracle_address', [32]],
+            ['oracle_status', 'u8'], // bool
+            ['name', 'string'],
+            ['endpoint_hash', [32]],
+            ['fee', 'u64']]
     }]]);
 
     let modify_oracle_instruction_payload = new InstructionPayload({
         variant: 5,
-        oracle_address: oracle_keypair.publicKey.toBytes()
+        oracle_address: oracle_keypair.publicKey.toBytes(),
+        oracle_status: oracle_status ? 1 : 0,
+        name: name,
+        endpoint_hash: endpoint_hash,
+        fee: fee
     });
 
     const modify_oracle_instruction_buffer = borsh.serialize(modify_oracle_schema, modify_oracle_instruction_payload);
@@ -42,13 +51,16 @@ const main = async () => {
 
     const pda_oracle = Web3.PublicKey.findProgramAddressSync([oracle_keypair.publicKey.toBuffer()],
         program_pubkey);
+    const pda_config = Web3.PublicKey.findProgramAddressSync([Buffer.from("config")], program_pubkey);
+    const pda_bond = Web3.PublicKey.findProgramAddressSync([Buffer.from("bond"), oracle_keypair.publicKey.toBuffer()],
+        program_pubkey);
 
     const instruction = new Web3.TransactionInstruction({
         keys: [
             {
-                pubkey: oracle_keypair.publicKey, // msg.sender
+                pubkey: admin_keypair.publicKey, // msg.sender, pays the oracle PDA rent
                 isSigner: true,
-                isWritable: false,
+                isWritable: true,
             },
             {
                 pubkey: pda_oracle[0],
@@ -59,6 +71,22 @@ const main = async () => {
                 pubkey: Web3.SystemProgram.programId,
                 isSigner: false,
                 isWritable: false
+            },
+            {
+                pubkey: pda_config[0],
+                isSigner: false,
+                isWritable: false
+            },
+            // only used at registration / reactivation: oracle posts its bond (reactivation checks it)
+            {
+                pubkey: oracle_keypair.publicKey,
+                isSigner: true,
+                isWritable: true
+            },
+            {
+                pubkey: pda_bond[0],
+                isSigner: false,
+                isWritable: true
             }
         ],
         data: modify_oracle_instruction_buffer,
@@ -70,11 +98,13 @@ const main = async () => {
     const tx = await Web3.sendAndConfirmTransaction(
         connection,
         transaction,
-        [oracle_keypair]
+        [admin_keypair, oracle_keypair]
     )
 
     console.log('Transaction:', tx)
 
 }
 
-main();
+module.exports = {
+    main
+}
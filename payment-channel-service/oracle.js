@@ -9,6 +9,7 @@ const connection = configuration.connection;
 
 const ipfs_helper = require('./utils/ipfs-helper-oracle.js');
 const state_helper = require('./utils/state-helper-oracle.js');
+const sequence_helper = require('./utils/sequence-helper-oracle.js');
 
 // PROGRAM DATA
 const program_address = configuration.program_id;
@@ -30,17 +31,58 @@ class Data extends Assignable {
 class ChannelState extends Assignable {
 }
 
-// To get info from blockchain
+class OracleSignature extends Assignable {
+}
+
+// To get info from blockchain (leading fields of ChannelState, decoded with deserializeUnchecked)
 const channel_state_schema = new Map([[ChannelState, {
     kind: 'struct',
     fields: [
         ['channel_id', 'string'],
-        ['oracle_address', [32]],
+        ['oracle_addresses', [[32]]],
+        ['oracle_threshold', 'u8'],
         ['current_status', 'u8'],
         ['num_of_stakeholders', 'u8'],
+        ['sequence', 'u64'],
     ]
 }]]);
 
+// Token as returned to the clients; this oracle adds its own signature (one of M-of-N)
+const token_schema = new Map([[Data, {
+    kind: 'struct',
+    fields: [
+        ['encoded_data', ["u8"]],
+        ['prev_state', 'string'],
+        ['sig_oracles', [OracleSignature]]
+    ]
+}], [OracleSignature, {
+    kind: 'struct',
+    fields: [
+        ['key_version', 'u32'],
+        ['signature', [64]]
+    ]
+}]]);
+
+// Oracle signs the whole core data (sig_sender included) with its current key
+const sign_oracle = (encoded_data) => {
+    return [new OracleSignature({
+        key_version: configuration.oracle_key_version,
+        signature: Nacl.sign.detached(Uint8Array.from(encoded_data), oracle_keypair.secretKey)
+    })];
+}
+
+// Nonce of the latest opened incarnation of the channel id (NONCE PDA, u64), 0 if never opened
+const get_channel_nonce = async (channelid) => {
+    const pda_nonce = Web3.PublicKey.findProgramAddressSync([Buffer.from("nonce"), Buffer.from(channelid)], program_pubkey);
+    const pda_nonce_info = await connection.getAccountInfo(pda_nonce[0]);
+
+    if (pda_nonce_info === null) {
+        return 0;
+    }
+
+    return Number(pda_nonce_info.data.readBigUInt64LE(0));
+}
+
 const open = async (pre_open_token_data_sig_sender) => {
 
     try {
@@ -53,6 +95,12 @@ const open = async (pre_open_token_data_sig_sender) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
+                ['mint', [32]],
+                ['oracles', [[32]]],
+                ['oracle_threshold', 'u8'],
+                ['emergency_timeout_slots', 'u64'],
             ]
         }]]);
 
@@ -74,19 +122,16 @@ const open = async (pre_open_token_data_sig_sender) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
+                ['mint', [32]],
+                ['oracles', [[32]]],
+                ['oracle_threshold', 'u8'],
+                ['emergency_timeout_slots', 'u64'],
                 ['sig_sender', [64]],
             ]
         }]]);
 
-        const open_token_schema = new Map([[Data, {
-            kind: 'struct',
-            fields: [
-                ['encoded_data', ["u8"]],
-                ['prev_state', 'string'],
-                ['sig_oracle', [64]]
-            ]
-        }]]);
-
         const pre_open_token_decoded_sender = borsh.deserialize(pre_open_token_schema_sig_sender, Data, pre_open_token_data_sig_sender);
         const open_token_core_data_decoded = borsh.deserialize(pre_open_token_core_data_schema_sender, Data, Buffer.from(pre_open_token_decoded_sender.encoded_data));
 
@@ -100,7 +145,7 @@ const open = async (pre_open_token_data_sig_sender) => {
         const pda_channel = Web3.PublicKey.findProgramAddressSync([Buffer.from(open_token_core_data_decoded.channelid)], program_pubkey);
         const pda_channel_info = await connection.getAccountInfo(pda_channel[0]);
 
-        if (!pda_channel_info === null) {
+        if (pda_channel_info !== null) {
             return Promise.reject(Error("Channel ID already exists (blockchain)"));
         }
 
@@ -126,6 +171,13 @@ const open = async (pre_open_token_data_sig_sender) => {
             return Promise.reject(Error("Balance defined within token is not valid"));
         }
 
+        // Channel must be bound to this oracle (otherwise its signature does not count)
+        const oracles = open_token_core_data_decoded.oracles.map((oracle) => Base58.encode(oracle));
+        if (!oracles.includes(configuration.oracle_address)) {
+            console.log('Oracles: ', oracles);
+            return Promise.reject(Error("Oracle is not one of the oracles defined within token"));
+        }
+
         const verify_sender_sig = Nacl.sign.detached.verify(
             Buffer.from(pre_open_token_decoded_sender.encoded_data),
             pre_open_token_decoded_sender.sig_sender,
@@ -136,6 +188,16 @@ const open = async (pre_open_token_data_sig_sender) => {
             throw new Error('Signature verification failed');
         }
 
+        // Token must be issued for the next incarnation of the channel id
+        const channel_nonce = await get_channel_nonce(open_token_core_data_decoded.channelid);
+        if (parseInt(open_token_core_data_decoded.channel_nonce) !== channel_nonce + 1) {
+            console.log('Channel nonce: ', parseInt(open_token_core_data_decoded.channel_nonce));
+            return Promise.reject(Error("Channel nonce defined within token is not valid"));
+        }
+
+        // Sequence must be the next one of the channel (reserved before signing)
+        sequence_helper.commit_sequence(open_token_core_data_decoded.channelid, parseInt(open_token_core_data_decoded.sequence));
+
         let open_token_core_data_oracle = new Data(
             {
                 action: open_token_core_data_decoded.action,
@@ -143,6 +205,12 @@ const open = async (pre_open_token_data_sig_sender) => {
                 address: open_token_core_data_decoded.address,
                 balance: open_token_core_data_decoded.balance,
                 sender: open_token_core_data_decoded.sender,
+                sequence: open_token_core_data_decoded.sequence,
+                channel_nonce: open_token_core_data_decoded.channel_nonce,
+                mint: open_token_core_data_decoded.mint,
+                oracles: open_token_core_data_decoded.oracles,
+                oracle_threshold: open_token_core_data_decoded.oracle_threshold,
+                emergency_timeout_slots: open_token_core_data_decoded.emergency_timeout_slots,
                 sig_sender: pre_open_token_decoded_sender.sig_sender
             }
         );
@@ -150,17 +218,15 @@ const open = async (pre_open_token_data_sig_sender) => {
         const open_token_core_data_encoded_oracle = borsh.serialize(open_token_core_data_schema_oracle, open_token_core_data_oracle);
 
         // return signed open_token
-        const sig_oracle_open = Nacl.sign.detached(Uint8Array.from(open_token_core_data_encoded_oracle), oracle_keypair.secretKey);
-
         let open_token_data = new Data(
             {
                 encoded_data: open_token_core_data_encoded_oracle,
                 prev_state: pre_open_token_decoded_sender.prev_state,
-                sig_oracle: sig_oracle_open
+                sig_oracles: sign_oracle(open_token_core_data_encoded_oracle)
             }
         );
 
-        let open_token_encoded = borsh.serialize(open_token_schema, open_token_data);
+        let open_token_encoded = borsh.serialize(token_schema, open_token_data);
 
         // IPFS + IPNS stuff
         const ipns_key = await ipfs_helper.create_ipns_key(open_token_core_data_decoded.channelid);
@@ -187,19 +253,12 @@ const join = async (pre_open_token_data_sig_sender) => {
             ['address', [32]],
             ['balance', 'u64'],
             ['sender', [32]],
+            ['sequence', 'u64'],
+            ['channel_nonce', 'u64'],
             ['sig_sender', [64]],
         ]
     }]]);
 
-    const join_token_schema = new Map([[Data, {
-        kind: 'struct',
-        fields: [
-            ['encoded_data', ["u8"]],
-            ['prev_state', 'string'],
-            ['sig_oracle', [64]]
-        ]
-    }]]);
-
     const join_token_core_data_schema_sender = new Map([[Data, {
         kind: 'struct',
         fields: [
@@ -208,6 +267,8 @@ const join = async (pre_open_token_data_sig_sender) => {
             ['address', [32]],
             ['balance', 'u64'],
             ['sender', [32]],
+            ['sequence', 'u64'],
+            ['channel_nonce', 'u64'],
         ]
     }]]);
 
@@ -245,13 +306,14 @@ const join = async (pre_open_token_data_sig_sender) => {
 
         const pda_channel = Web3.PublicKey.findProgramAddressSync([Buffer.from(join_token_core_data_decoded.channelid)], program_pubkey);
         const pda_channel_info = await connection.getAccountInfo(pda_channel[0]);
-        const channel_info_decoded = borsh.deserialize(channel_state_schema, ChannelState, pda_channel_info.data);
 
         if (pda_channel_info === null) {
             console.log('Channel not found')
             return Promise.reject(Error("Channel ID does not exist (blockchain)"));
         }
 
+        const channel_info_decoded = borsh.deserializeUnchecked(channel_state_schema, ChannelState, pda_channel_info.data);
+
         if (channel_info_decoded.current_status != 1) {
             console.log('Channel not in open state')
             console.log('Current status: ', channel_info_decoded.current_status);
@@ -280,6 +342,16 @@ const join = async (pre_open_token_data_sig_sender) => {
             return Promise.reject(Error("Balance defined within token is not valid"));
         }
 
+        // Token must be issued for the current incarnation of the channel id
+        const channel_nonce = await get_channel_nonce(join_token_core_data_decoded.channelid);
+        if (parseInt(join_token_core_data_decoded.channel_nonce) !== channel_nonce) {
+            console.log('Channel nonce: ', parseInt(join_token_core_data_decoded.channel_nonce));
+            return Promise.reject(Error("Channel nonce defined within token is not valid"));
+        }
+
+        // Sequence must be the next one of the channel (reserved before signing)
+        sequence_helper.commit_sequence(join_token_core_data_decoded.channelid, parseInt(join_token_core_data_decoded.sequence));
+
         let join_token_core_data_oracle = new Data(
             {
                 action: join_token_core_data_decoded.action,
@@ -287,6 +359,8 @@ const join = async (pre_open_token_data_sig_sender) => {
                 address: join_token_core_data_decoded.address,
                 balance: join_token_core_data_decoded.balance,
                 sender: join_token_core_data_decoded.sender,
+                sequence: join_token_core_data_decoded.sequence,
+                channel_nonce: join_token_core_data_decoded.channel_nonce,
                 sig_sender: pre_join_token_decoded_sender.sig_sender
             }
         );
@@ -294,17 +368,15 @@ const join = async (pre_open_token_data_sig_sender) => {
         const join_token_core_data_encoded_oracle = borsh.serialize(join_token_core_data_schema_oracle, join_token_core_data_oracle);
 
         // return signed join_token
-        const sig_oracle_join = Nacl.sign.detached(Uint8Array.from(join_token_core_data_encoded_oracle), oracle_keypair.secretKey);
-
         let join_token_data = new Data(
             {
                 encoded_data: join_token_core_data_encoded_oracle,
                 prev_state: pre_join_token_decoded_sender.prev_state,
-                sig_oracle: sig_oracle_join
+                sig_oracles: sign_oracle(join_token_core_data_encoded_oracle)
             }
         );
 
-        let join_token_encoded = borsh.serialize(join_token_schema, join_token_data);
+        let join_token_encoded = borsh.serialize(token_schema, join_token_data);
         // console.log('join_token_encoded hex: ', join_token_encoded.toString('hex'));
 
         return Promise.resolve(join_token_encoded);
@@ -353,6 +425,8 @@ const update = async (tx_token_sender_signed, sender_public_key, amount, receive
             fields.push([`balance${i}`, 'u64']);
         }
         fields.push(['sender', [32]]);
+        fields.push(['sequence', 'u64']);
+        fields.push(['channel_nonce', 'u64']);
         fields.push(['num_of_stakeholders', 'u8']);
 
         const off_chain_token_data_schema_sender = new Map([[Data, {
@@ -444,6 +518,16 @@ const update = async (tx_token_sender_signed, sender_public_key, amount, receive
             return Promise.reject('State is different!');
         }
 
+        // Token must be issued for the current incarnation of the channel id
+        const channel_nonce = await get_channel_nonce(off_chain_token_data_decoded.channelid);
+        if (parseInt(off_chain_token_data_decoded.channel_nonce) !== channel_nonce) {
+            console.log('Channel nonce: ', parseInt(off_chain_token_data_decoded.channel_nonce));
+            return Promise.reject(Error("Channel nonce defined within token is not valid"));
+        }
+
+        // Sequence must be the next one of the channel (reserved before signing)
+        sequence_helper.commit_sequence(off_chain_token_data_decoded.channelid, parseInt(off_chain_token_data_decoded.sequence));
+
         fields.push(['sig_sender', [64]],);
 
         const off_chain_token_data_schema = new Map([[Data, {
@@ -451,15 +535,6 @@ const update = async (tx_token_sender_signed, sender_public_key, amount, receive
             fields
         }]]);
 
-        const off_chain_tx_schema = new Map([[Data, {
-            kind: 'struct',
-            fields: [
-                ['encoded_data', ["u8"]],
-                ['prev_state', 'string'],
-                ['sig_oracle', [64]]
-            ]
-        }]]);
-
         // Generate off-chain tx data
         let off_chain_tx_data = new Data({
             action: off_chain_token_data_decoded.action,
@@ -470,24 +545,23 @@ const update = async (tx_token_sender_signed, sender_public_key, amount, receive
             off_chain_tx_data["balance" + (index + 1)] = item.balance;
         });
         off_chain_tx_data.sender = off_chain_token_data_decoded.sender;
+        off_chain_tx_data.sequence = off_chain_token_data_decoded.sequence;
+        off_chain_tx_data.channel_nonce = off_chain_token_data_decoded.channel_nonce;
         off_chain_tx_data.num_of_stakeholders = off_chain_token_data_decoded.num_of_stakeholders;
         off_chain_tx_data.sig_sender = pre_tx_token_decoded.sig_sender;
         // serialize off_chain_tx_data
         const off_chain_tx_data_encoded = borsh.serialize(off_chain_token_data_schema, off_chain_tx_data);
 
-        // sign off_chain_tx_data
-        const off_chain_tx_data_encoded_sig_oracle = Nacl.sign.detached(Buffer.from(off_chain_tx_data_encoded), oracle_keypair.secretKey);
-
         const prev_state_cid = await ipfs_helper.resolve_cid_ipns(off_chain_token_data_decoded.channelid);
 
         // serialize off_chain_tx
         const off_chain_tx = new Data({
             encoded_data: off_chain_tx_data_encoded,
             prev_state: prev_state_cid, // get from IPFS
-            sig_oracle: off_chain_tx_data_encoded_sig_oracle
+            sig_oracles: sign_oracle(off_chain_tx_data_encoded)
         });
 
-        const off_chain_tx_encoded = borsh.serialize(off_chain_tx_schema, off_chain_tx);
+        const off_chain_tx_encoded = borsh.serialize(token_schema, off_chain_tx);
 
         // IPFS + IPNS stuff
         const res_store = await ipfs_helper.store_data_ipfs(off_chain_tx_encoded);
@@ -513,6 +587,8 @@ const leave = async (leave_data_signed_encoded_sender) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
             ]
         }]]);
 
@@ -524,19 +600,12 @@ const leave = async (leave_data_signed_encoded_sender) => {
                 ['address', [32]],
                 ['balance', 'u64'],
                 ['sender', [32]],
+                ['sequence', 'u64'],
+                ['channel_nonce', 'u64'],
                 ['sig_sender', [64]],
             ]
         }]]);
 
-        const leave_token_schema = new Map([[Data, {
-            kind: 'struct',
-            fields: [
-                ['encoded_data', ["u8"]],
-                ['prev_state', 'string'],
-                ['sig_oracle', [64]],
-            ]
-        }]]);
-
         const leave_token_core_data_decoded = borsh.deserialize(leave_token_core_data_schema, Data, leave_data_signed_encoded_sender);
 
         const last_off_chain_state = await state_helper.get_last_channel_state(leave_token_core_data_decoded.channelid);
@@ -562,6 +631,8 @@ const leave = async (leave_data_signed_encoded_sender) => {
             address: leave_token_core_data_decoded.address,
             balance: leave_token_core_data_decoded.balance,
             sender: leave_token_core_data_decoded.sender,
+            sequence: leave_token_core_data_decoded.sequence,
+            channel_nonce: leave_token_core_data_decoded.channel_nonce,
         });
 
         const data_for_sig_verification_encoded = borsh.serialize(pre_leave_token_core_data_schema, data_for_sig_verification);
@@ -573,8 +644,15 @@ const leave = async (leave_data_signed_encoded_sender) => {
             return Promise.reject('Invalid signature!');
         }
 
-        // sign data oracle (off-chain)
-        const leave_token_signed_oracle = Nacl.sign.detached(Buffer.from(leave_data_signed_encoded_sender), oracle_keypair.secretKey);
+        // Token must be issued for the current incarnation of the channel id
+        const channel_nonce = await get_channel_nonce(leave_token_core_data_decoded.channelid);
+        if (parseInt(leave_token_core_data_decoded.channel_nonce) !== channel_nonce) {
+            console.log('Channel nonce: ', parseInt(leave_token_core_data_decoded.channel_nonce));
+            return Promise.reject(Error("Channel nonce defined within token is not valid"));
+        }
+
+        // Sequence must be the next one of the channel (reserved before signing)
+        sequence_helper.commit_sequence(leave_token_core_data_decoded.channelid, parseInt(leave_token_core_data_decoded.sequence));
 
         // get prev state cid
         const prev_state_cid = await ipfs_helper.resolve_cid_ipns(leave_token_core_data_decoded.channelid);
@@ -583,10 +661,10 @@ const leave = async (leave_data_signed_encoded_sender) => {
         let leave_token = new Data({
             encoded_data: leave_data_signed_encoded_sender,
             prev_state: prev_state_cid,
-            sig_oracle: leave_token_signed_oracle,
+            sig_oracles: sign_oracle(leave_data_signed_encoded_sender),
         });
 
-        const leave_token_encoded = borsh.serialize(leave_token_schema, leave_token);
+        const leave_token_encoded = borsh.serialize(token_schema, leave_token);
 
         // ipns+ipfs
         const res_store = await ipfs_helper.store_data_ipfs(leave_token_encoded);
@@ -601,7 +679,19 @@ const leave = async (leave_data_signed_encoded_sender) => {
 
 }
 
+// Sequence the next token of the channel has to carry
+const next_sequence = async (channelid) => {
+    return Promise.resolve(sequence_helper.next_sequence(channelid));
+}
+
+// Nonce tokens of the (currently opened) channel have to carry; open takes the next one
+const channel_nonce = async (channelid) => {
+    return get_channel_nonce(channelid);
+}
+
 module.exports = {
+    next_sequence,
+    channel_nonce,
     open,
     join,
     update,
@@ -0,0 +1,53 @@
+const fs = require("fs");
+
+const configuration = require("../configuration.js");
+
+// Last sequence handed out per channel, persisted as { "<channel_id>": <sequence> }.
+// Every token (on-chain and off-chain) of a channel takes the next value, so the program can
+// order them (it rejects tokens whose sequence is not greater than the stored one).
+const sequence_store_path = configuration.sequence_store_path;
+
+function load_sequences() {
+    if (!fs.existsSync(sequence_store_path)) {
+        return {};
+    }
+
+    return JSON.parse(fs.readFileSync(sequence_store_path, 'utf8'));
+}
+
+function store_sequences(sequences) {
+    // write + rename, so a crash never leaves a truncated store behind
+    const tmp_path = sequence_store_path + '.tmp';
+    fs.writeFileSync(tmp_path, JSON.stringify(sequences, null, 2));
+    fs.renameSync(tmp_path, sequence_store_path);
+}
+
+function get_last_sequence(channelid) {
+    const sequences = load_sequences();
+
+    return sequences.hasOwnProperty(channelid) ? sequences[channelid] : 0;
+}
+
+function next_sequence(channelid) {
+    return get_last_sequence(channelid) + 1;
+}
+
+// Reserves `sequence` for the channel; only the next one is accepted. Runs synchronously, so two
+// requests for the same channel can not both take the same value.
+function commit_sequence(channelid, sequence) {
+    const sequences = load_sequences();
+    const last_sequence = sequences.hasOwnProperty(channelid) ? sequences[channelid] : 0;
+
+    if (sequence !== last_sequence + 1) {
+        throw new Error("Invalid sequence " + sequence + ", expected " + (last_sequence + 1));
+    }
+
+    sequences[channelid] = sequence;
+    store_sequences(sequences);
+}
+
+module.exports = {
+    get_last_sequence,
+    next_sequence,
+    commit_sequence
+}
@@ -27,6 +27,9 @@ class Data extends Assignable {
 class StakeholderState extends Assignable {
 }
 
+class OracleSignature extends Assignable {
+}
+
 async function decode_channel_state(encodedData) { // this is relevant only for actions: 1 - open; join (i.e., where only one state (= tuple address, balance) is present)
 
     const action_schema = new Map([
@@ -38,7 +41,13 @@ async function decode_channel_state(encodedData) { // this is relevant only for
         fields: [
             ['encoded_data', ["u8"]],
             ['prev_state', 'string'],
-            ['sig_oracle', [64]]
+            ['sig_oracles', [OracleSignature]]
+        ]
+    }], [OracleSignature, {
+        kind: 'struct',
+        fields: [
+            ['key_version', 'u32'],
+            ['signature', [64]]
         ]
     }]]);
 
@@ -48,16 +57,26 @@ async function decode_channel_state(encodedData) { // this is relevant only for
 
     if (action_data.action == 1 || action_data.action == 2 || action_data.action == 4) { // open or join
 
+        const fields = [
+            ['action', 'u8'],
+            ['channelid', 'string'],
+            ['address', [32]], // !
+            ['balance', 'u64'], // !
+            ['sender', [32]],
+            ['sequence', 'u64'],
+            ['channel_nonce', 'u64'],
+        ];
+        if (action_data.action == 1) { // open token also carries the channel configuration
+            fields.push(['mint', [32]]);
+            fields.push(['oracles', [[32]]]);
+            fields.push(['oracle_threshold', 'u8']);
+            fields.push(['emergency_timeout_slots', 'u64']);
+        }
+        fields.push(['sig_sender', [64]]);
+
         const data_decoded_schema = new Map([[Data, {
             kind: 'struct',
-            fields: [
-                ['action', 'u8'],
-                ['channelid', 'string'],
-                ['address', [32]], // !
-                ['balance', 'u64'], // !
-                ['sender', [32]],
-                ['sig_sender', [64]],
-            ]
+            fields
         }]]);
 
         const data_decoded = borsh.deserialize(data_decoded_schema, Data, Buffer.from(decoded.encoded_data, 'hex'));
@@ -90,9 +109,11 @@ async function decode_channel_state(encodedData) { // this is relevant only for
             channel_id: data_decoded.channelid,
             state: state,
             sender: Base58.encode(data_decoded.sender),
+            sequence: parseInt(data_decoded.sequence),
+            channel_nonce: parseInt(data_decoded.channel_nonce),
             state_prev: decoded.prev_state,
             sender_sig: data_decoded.sig_sender,
-            oracle_sig: decoded.sig_oracle
+            oracle_sigs: decoded.sig_oracles
         });
 
     } else if (action_data.action == 3) { // if off-chain tx, then we have different schema, and based on encoded_data (last 1 byte we get num of participants)
@@ -114,6 +135,8 @@ async function decode_channel_state(encodedData) { // this is relevant only for
             fields.push([`balance${i}`, 'u64']);
         }
         fields.push(['sender', [32]]);
+        fields.push(['sequence', 'u64']);
+        fields.push(['channel_nonce', 'u64']);
         fields.push(['num_of_stakeholders', 'u8']);
         fields.push(['sig_sender', [64]]);
 
@@ -155,9 +178,11 @@ async function decode_channel_state(encodedData) { // this is relevant only for
             channel_id: data_decoded.channelid,
             state: state,
             sender: Base58.encode(data_decoded.sender),
+            sequence: parseInt(data_decoded.sequence),
+            channel_nonce: parseInt(data_decoded.channel_nonce),
             state_prev: decoded.prev_state,
             sender_sig: data_decoded.sig_sender,
-            oracle_sig: decoded.sig_oracle
+            oracle_sigs: decoded.sig_oracles
         });
 
     }
@@ -249,6 +274,8 @@ async function generate_off_chain_tx_schema(num_of_stakeholders) {
         fields.push([`balance${i}`, 'u64']);
     }
     fields.push(['sender', [32]]);
+    fields.push(['sequence', 'u64']);
+    fields.push(['channel_nonce', 'u64']);
     fields.push(['num_of_stakeholders', 'u8']);
 
     const off_chain_token_data_schema = new Map([[Data, {
@@ -0,0 +1,18 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+// bond_pda = ("bond"+oracle_address); lamports posted by the oracle at registration,
+// slashed by ProveOracleFraud (see prove_oracle_fraud_processor)
+pub const BOND_SEED: &[u8] = b"bond";
+
+// Bond posted by every oracle at registration (1 SOL)
+pub const ORACLE_BOND_LAMPORTS: u64 = 1_000_000_000;
+
+// Share of a slashed bond paid to the prover; the rest goes to the stakeholders of the channel
+pub const FRAUD_BOUNTY_PERCENT: u64 = 10;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct BondState {
+    pub oracle_address: Pubkey,
+    pub amount: u64, // lamports at stake (on top of rent)
+}
@@ -0,0 +1,225 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use payment_channel_token::{TokenPayload, UpdateTokenData, ACTION_UPDATE};
+
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
+
+pub fn challenge_leave(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    challenge_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // challenger (channel member)
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_challenger_account = next_account_info(account_info_iter)?; // stakeholder PDA of msg_sender
+    let pda_leaving_stakeholder_account = next_account_info(account_info_iter)?; // stakeholder PDA in status 4 (closing)
+    let sysvar_account = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let challenge_token_decoded =
+        match TokenPayload::try_from_slice(challenge_token_encoded.as_slice()) {
+            Ok(payload) => payload,
+            Err(_) => {
+                msg!("Failed to decode challenge_token");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    let challenge_token_core_data_decoded =
+        match UpdateTokenData::try_from_slice(challenge_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode challenge_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    // Challenge must be an off-chain state (action = 3)
+    if challenge_token_core_data_decoded.action != ACTION_UPDATE {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let (pda_channel, _) = Pubkey::find_program_address(
+        &[challenge_token_core_data_decoded.channelid.as_bytes()],
+        program_id,
+    );
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != challenge_token_core_data_decoded.channelid {
+        msg!("challenge_token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data
+        .check_channel_nonce(challenge_token_core_data_decoded.channel_nonce)?;
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    // Token must not be older than (or conflict with) the checkpointed state
+    pda_channel_account_data.check_checkpoint(
+        challenge_token_core_data_decoded.sequence,
+        &challenge_token_decoded.encoded_data,
+    )?;
+
+    // Load and check challenger (must be member of the channel)
+    let (pda_challenger, _) = Pubkey::find_program_address(
+        &[
+            challenge_token_core_data_decoded.channelid.as_bytes(),
+            msg_sender.key.as_ref(),
+        ],
+        program_id,
+    );
+
+    if pda_challenger != *pda_challenger_account.key {
+        msg!("pda_challenger != pda_challenger_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_challenger_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_challenger_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    if pda_challenger_account_data.status != 2 && pda_challenger_account_data.status != 4 {
+        msg!("msg.sender is not part of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Load and check leaving stakeholder
+    let mut pda_leaving_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_leaving_stakeholder_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize leaving Stakeholder PDA data");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    let (pda_leaving_stakeholder, _) = Pubkey::find_program_address(
+        &[
+            challenge_token_core_data_decoded.channelid.as_bytes(),
+            pda_leaving_stakeholder_account_data
+                .stakeholder_address
+                .as_ref(),
+        ],
+        program_id,
+    );
+
+    if pda_leaving_stakeholder != *pda_leaving_stakeholder_account.key {
+        msg!("pda_leaving_stakeholder != pda_leaving_stakeholder_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    if pda_leaving_stakeholder_account_data.status != 4 {
+        msg!("Stakeholder status != closing");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    let clock = Clock::get()?;
+    if clock.slot >= pda_leaving_stakeholder_account_data.closing_slot {
+        msg!("Dispute window is over");
+        return Err(PaymentChannelError::DisputeWindowClosed.into());
+    }
+
+    // Challenge must be based on a later state than the one the leave was based on
+    if challenge_token_core_data_decoded.sequence <= pda_leaving_stakeholder_account_data.sequence {
+        msg!(
+            "Token sequence {} is not greater than leave sequence {}",
+            challenge_token_core_data_decoded.sequence,
+            pda_leaving_stakeholder_account_data.sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    // Leaving stakeholder must be part of the provided state
+    let challenged_balance = match challenge_token_core_data_decoded
+        .balance_of(&pda_leaving_stakeholder_account_data.stakeholder_address)
+    {
+        Some(balance) => balance,
+        None => {
+            msg!("Leaving stakeholder is not part of the provided state");
+            return Err(PaymentChannelError::StateMismatch.into());
+        }
+    };
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, challenge_token_decoded.sig_oracles.len())?;
+
+    // Oracle may be suspended meanwhile; pending leaves can still be challenged
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        challenge_token_decoded.oracle_preimage(),
+        &challenge_token_decoded.sig_oracles,
+        true,
+    )?;
+
+    // Later state replaces the pending leave balance (closing_slot is left unchanged)
+    msg!(
+        "Leave challenged, pending amount {} -> {}",
+        pda_leaving_stakeholder_account_data.balance,
+        challenged_balance
+    );
+
+    pda_leaving_stakeholder_account_data.balance = challenged_balance;
+    pda_leaving_stakeholder_account_data.sequence = challenge_token_core_data_decoded.sequence;
+
+    pda_leaving_stakeholder_account_data
+        .serialize(&mut &mut pda_leaving_stakeholder_account.data.borrow_mut()[..])?;
+
+    if challenge_token_core_data_decoded.sequence > pda_channel_account_data.sequence {
+        pda_channel_account_data.sequence = challenge_token_core_data_decoded.sequence;
+    }
+    pda_channel_account_data.last_checkpoint_slot = clock.slot;
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
@@ -1,10 +1,117 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{entrypoint::ProgramResult, hash::hash, msg, pubkey::Pubkey};
+
+use crate::config_state::CONFIG_SEED;
+use crate::error::PaymentChannelError;
+use crate::nonce_state::NONCE_SEED;
+use crate::oracle_state::OracleQuorum;
+
+// Max length (bytes) of the IPFS path (e.g. "/ipfs/<cid>") of a checkpointed state
+pub const MAX_CHECKPOINT_CID_LEN: usize = 96;
+
+// Seeds of the other program PDAs. PDA seeds are concatenated: the channel PDA of "config" is the
+// program config PDA, the one of "nonce"+channelid the NONCE PDA of channelid - such channel ids
+// are not accepted
+pub const RESERVED_CHANNEL_ID_PREFIXES: [&[u8]; 2] = [NONCE_SEED, CONFIG_SEED];
+
+pub fn check_channel_id(channel_id: &str) -> ProgramResult {
+    if RESERVED_CHANNEL_ID_PREFIXES
+        .iter()
+        .any(|prefix| channel_id.as_bytes().starts_with(prefix))
+    {
+        msg!("Channel ID {} starts with a reserved seed", channel_id);
+        return Err(PaymentChannelError::ReservedChannelId.into());
+    }
+
+    Ok(())
+}
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct ChannelState {
     pub channel_id: String,             // channelid
-    pub oracle_address: Pubkey,         // address of oracle
+    pub oracle_addresses: Vec<Pubkey>,  // oracles chosen at open (registry entries = oracle_pda)
+    pub oracle_threshold: u8,           // min number of oracle signatures per token
     pub current_status: u8,             // 1 = opened, 2 = closed
     pub num_of_active_stakeholders: u8, // number of stakeholders
+    pub sequence: u64,                  // sequence of the latest accepted oracle-signed token
+    pub mint: Pubkey,                   // SPL Token mint of the channel, Pubkey::default() = SOL
+    pub total_deposits: u64,            // funds deposited by stakeholders and not yet paid out
+    pub last_checkpoint_slot: u64,      // slot of the latest accepted oracle-signed token
+    pub emergency_timeout_slots: u64, // slots without oracle activity after which EmergencyExit opens
+    // Latest checkpointed off-chain state (see checkpoint_processor)
+    pub checkpoint_hash: [u8; 32], // sha256 of the oracle-signed encoded_data
+    pub checkpoint_sequence: u64,  // sequence of the checkpointed state, 0 = none yet
+    pub checkpoint_cid: String,    // IPFS path the checkpointed state is published under
+    // Latest checkpointed Merkle root of (address, balance, sequence) leaves, see merkle.rs
+    pub balances_root: [u8; 32],
+    pub balances_root_sequence: u64, // 0 = none yet
+    pub opener: Pubkey, // paid the channel PDA rent, refunded once the channel is closed
+    pub channel_nonce: u64, // incarnation of the channel id, see nonce_state.rs
+}
+
+impl ChannelState {
+    pub fn oracle_quorum(&self) -> OracleQuorum<'_> {
+        OracleQuorum {
+            oracles: &self.oracle_addresses,
+            threshold: self.oracle_threshold,
+        }
+    }
+
+    // Tokens are bound to the incarnation of the channel id they were signed for, so tokens of a
+    // closed channel can not be replayed once the channel id is opened again
+    pub fn check_channel_nonce(&self, channel_nonce: u64) -> ProgramResult {
+        if channel_nonce != self.channel_nonce {
+            msg!(
+                "Token channel nonce {} != channel nonce {}",
+                channel_nonce,
+                self.channel_nonce
+            );
+            return Err(PaymentChannelError::ChannelNonceMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    // Tokens older than the checkpoint are stale; a token of the checkpointed sequence must be
+    // the checkpointed state itself
+    pub fn check_checkpoint(&self, sequence: u64, encoded_data: &[u8]) -> ProgramResult {
+        if sequence < self.checkpoint_sequence {
+            msg!(
+                "Token sequence {} is older than checkpoint sequence {}",
+                sequence,
+                self.checkpoint_sequence
+            );
+            return Err(PaymentChannelError::StaleSequence.into());
+        }
+
+        if sequence == self.checkpoint_sequence
+            && hash(encoded_data).to_bytes() != self.checkpoint_hash
+        {
+            msg!(
+                "Token does not match checkpointed state {}",
+                self.checkpoint_cid
+            );
+            return Err(PaymentChannelError::CheckpointMismatch.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_error::ProgramError;
+
+    #[test]
+    fn channel_ids_with_reserved_prefix_are_rejected() {
+        let reserved = Err(ProgramError::from(PaymentChannelError::ReservedChannelId));
+
+        assert_eq!(check_channel_id("nonce"), reserved);
+        assert_eq!(check_channel_id("nonceabc"), reserved);
+        assert_eq!(check_channel_id("config"), reserved);
+
+        assert_eq!(check_channel_id("abc"), Ok(()));
+        assert_eq!(check_channel_id("channel-nonce"), Ok(()));
+    }
 }
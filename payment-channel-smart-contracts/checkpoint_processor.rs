@@ -0,0 +1,283 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    hash::hash,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use payment_channel_token::{
+    CheckpointTokenData, TokenPayload, UpdateTokenData, ACTION_CHECKPOINT, ACTION_UPDATE,
+};
+
+use crate::channel_state::{ChannelState, MAX_CHECKPOINT_CID_LEN};
+use crate::error::PaymentChannelError;
+use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
+
+// Anchors the off-chain state chain (IPFS, linked by `prev_state`) on-chain: the channel stores the
+// hash, sequence and IPFS path of an oracle-signed state. Leave, challenge and settle tokens are
+// checked against it (see ChannelState::check_checkpoint).
+// The IPFS path comes with a cid token (action = 8) in which the oracles sign it together with
+// the hash of the checkpointed state; the state itself is bound to those signatures by its hash.
+pub fn checkpoint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    checkpoint_token_encoded: Vec<u8>,
+    cid_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // channel member
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_stakeholder_account = next_account_info(account_info_iter)?; // stakeholder PDA of msg_sender
+    let sysvar_account = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles` of the cid token, same order
+    // Remaining accounts: stakeholder PDAs of every stakeholder listed in the token, in the same
+    // order as in the token; on-chain balances of the active ones are set to the checkpointed ones
+    // (used by EmergencyExit)
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let checkpoint_token_decoded =
+        match TokenPayload::try_from_slice(checkpoint_token_encoded.as_slice()) {
+            Ok(payload) => payload,
+            Err(_) => {
+                msg!("Failed to decode checkpoint_token");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    let checkpoint_token_core_data_decoded =
+        match UpdateTokenData::try_from_slice(checkpoint_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode checkpoint_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    // Only off-chain states (action = 3) are checkpointed
+    if checkpoint_token_core_data_decoded.action != ACTION_UPDATE {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    let cid_token_decoded = match TokenPayload::try_from_slice(cid_token_encoded.as_slice()) {
+        Ok(payload) => payload,
+        Err(_) => {
+            msg!("Failed to decode cid_token");
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
+        }
+    };
+
+    let cid_token_core_data_decoded =
+        match CheckpointTokenData::try_from_slice(cid_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode cid_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    if cid_token_core_data_decoded.action != ACTION_CHECKPOINT {
+        msg!("Wrong 'action' defined within provided cid token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    if cid_token_core_data_decoded.channelid != checkpoint_token_core_data_decoded.channelid
+        || cid_token_core_data_decoded.sequence != checkpoint_token_core_data_decoded.sequence
+        || cid_token_core_data_decoded.channel_nonce
+            != checkpoint_token_core_data_decoded.channel_nonce
+        || cid_token_core_data_decoded.state_hash
+            != hash(&checkpoint_token_decoded.encoded_data).to_bytes()
+    {
+        msg!("cid token does not refer to the provided checkpoint token");
+        return Err(PaymentChannelError::CheckpointMismatch.into());
+    }
+
+    let cid = cid_token_core_data_decoded.cid;
+
+    if cid.is_empty() || cid.len() > MAX_CHECKPOINT_CID_LEN {
+        msg!("Invalid checkpoint cid");
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let channel_id = checkpoint_token_core_data_decoded.channelid.as_str();
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("checkpoint_token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data
+        .check_channel_nonce(checkpoint_token_core_data_decoded.channel_nonce)?;
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    // Load and check msg.sender (must be member of the channel)
+    let (pda_stakeholder, _) = Pubkey::find_program_address(
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
+        program_id,
+    );
+
+    if pda_stakeholder != *pda_stakeholder_account.key {
+        msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_stakeholder_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    if pda_stakeholder_account_data.status != 2 && pda_stakeholder_account_data.status != 4 {
+        msg!("msg.sender is not part of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Checkpoint must be newer than both the previous checkpoint and the latest on-chain action
+    let sequence = checkpoint_token_core_data_decoded.sequence;
+
+    if sequence <= pda_channel_account_data.checkpoint_sequence
+        || sequence <= pda_channel_account_data.sequence
+    {
+        msg!(
+            "Token sequence {} is not greater than channel sequence {} / checkpoint sequence {}",
+            sequence,
+            pda_channel_account_data.sequence,
+            pda_channel_account_data.checkpoint_sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    let total_amount = checkpoint_token_core_data_decoded
+        .state
+        .iter()
+        .try_fold(0u64, |total, (_, balance)| total.checked_add(*balance))
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    if total_amount > pda_channel_account_data.total_deposits {
+        msg!("Token balances exceed channel deposits");
+        return Err(PaymentChannelError::InsufficientFunds.into());
+    }
+
+    // Every stakeholder still in the channel is part of the checkpoint, once
+    let mut addresses: Vec<&Pubkey> = checkpoint_token_core_data_decoded
+        .state
+        .iter()
+        .map(|(address, _)| address)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+
+    if checkpoint_token_core_data_decoded.num_of_stakeholders
+        != pda_channel_account_data.num_of_active_stakeholders
+        || addresses.len() != checkpoint_token_core_data_decoded.state.len()
+    {
+        msg!("Token does not include all active stakeholders of the channel");
+        return Err(PaymentChannelError::StateMismatch.into());
+    }
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, cid_token_decoded.sig_oracles.len())?;
+
+    // Verification of 'oracle' signatures (M-of-N) over the cid token
+    msg!("Verification of cid token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        cid_token_decoded.oracle_preimage(),
+        &cid_token_decoded.sig_oracles,
+        false,
+    )?;
+
+    // Update on-chain balances of all (active) stakeholders
+    for (address, balance) in checkpoint_token_core_data_decoded.state.iter() {
+        let pda_member_account = next_account_info(account_info_iter)?;
+
+        let (pda_member, _) =
+            Pubkey::find_program_address(&[channel_id.as_bytes(), address.as_ref()], program_id);
+
+        if pda_member != *pda_member_account.key {
+            msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+            return Err(PaymentChannelError::InvalidSeeds.into());
+        }
+
+        let mut pda_member_account_data =
+            match try_from_slice_unchecked::<StakeholderState>(&pda_member_account.data.borrow()) {
+                Ok(data) => data,
+                Err(_) => {
+                    msg!("Failed to deserialize Stakeholder PDA data of {}", address);
+                    return Err(PaymentChannelError::AccountNotInitialized.into());
+                }
+            };
+
+        match pda_member_account_data.status {
+            2 => {
+                // assign values
+                pda_member_account_data.balance = *balance;
+                pda_member_account_data.sequence = sequence;
+
+                pda_member_account_data
+                    .serialize(&mut &mut pda_member_account.data.borrow_mut()[..])?;
+            }
+            // Pending leaves are only changed by challenge_leave
+            4 => {}
+            _ => {
+                msg!("Stakeholder {} is not part of the channel", address);
+                return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+            }
+        }
+    }
+
+    // ASSIGN VALUES
+    pda_channel_account_data.checkpoint_hash =
+        hash(&checkpoint_token_decoded.encoded_data).to_bytes();
+    pda_channel_account_data.checkpoint_sequence = sequence;
+    pda_channel_account_data.checkpoint_cid = cid;
+    pda_channel_account_data.last_checkpoint_slot = Clock::get()?.slot;
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Checkpoint {} at sequence {}",
+        pda_channel_account_data.checkpoint_cid,
+        sequence
+    );
+
+    Ok(())
+}
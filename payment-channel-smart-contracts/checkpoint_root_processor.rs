@@ -0,0 +1,136 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use payment_channel_token::{RootTokenData, TokenPayload, ACTION_BALANCES_ROOT};
+
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::try_from_slice_unchecked;
+
+// For channels too large to pass every balance on-chain; stakeholders leave with an inclusion
+// proof against the root (see leave_with_proof_processor)
+pub fn checkpoint_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    root_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?;
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let sysvar_account = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let root_token_decoded = match TokenPayload::try_from_slice(root_token_encoded.as_slice()) {
+        Ok(payload) => payload,
+        Err(_) => {
+            msg!("Failed to decode root_token");
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
+        }
+    };
+
+    let root_token_core_data_decoded =
+        match RootTokenData::try_from_slice(root_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode root_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    if root_token_core_data_decoded.action != ACTION_BALANCES_ROOT {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let channel_id = root_token_core_data_decoded.channelid.as_str();
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("root_token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data.check_channel_nonce(root_token_core_data_decoded.channel_nonce)?;
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    // Root must be newer than the previous root, the latest checkpoint and the latest on-chain action
+    let sequence = root_token_core_data_decoded.sequence;
+
+    if sequence <= pda_channel_account_data.balances_root_sequence
+        || sequence <= pda_channel_account_data.checkpoint_sequence
+        || sequence <= pda_channel_account_data.sequence
+    {
+        msg!(
+            "Token sequence {} is not greater than channel sequence {} / root sequence {} / checkpoint sequence {}",
+            sequence,
+            pda_channel_account_data.sequence,
+            pda_channel_account_data.balances_root_sequence,
+            pda_channel_account_data.checkpoint_sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    if root_token_core_data_decoded.total_balance > pda_channel_account_data.total_deposits {
+        msg!("Token balances exceed channel deposits");
+        return Err(PaymentChannelError::InsufficientFunds.into());
+    }
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, root_token_decoded.sig_oracles.len())?;
+
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        root_token_decoded.oracle_preimage(),
+        &root_token_decoded.sig_oracles,
+        false,
+    )?;
+
+    // ASSIGN VALUES
+    pda_channel_account_data.balances_root = root_token_core_data_decoded.root;
+    pda_channel_account_data.balances_root_sequence = sequence;
+    pda_channel_account_data.last_checkpoint_slot = Clock::get()?.slot;
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    msg!("Balances root checkpointed at sequence {}", sequence);
+
+    Ok(())
+}
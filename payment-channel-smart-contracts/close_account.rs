@@ -0,0 +1,81 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
+use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{close_vault, vault_amount};
+
+// Close a program-owned account (PDA): all of its lamports go to `destination`, data is wiped
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = account.lamports();
+
+    **account.try_borrow_mut_lamports()? = 0;
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+// Close stakeholder PDA; rent is refunded to whoever paid for it (opener / inviter)
+pub fn close_stakeholder(
+    pda_stakeholder_account: &AccountInfo,
+    pda_stakeholder_account_data: &StakeholderState,
+    rent_payer_account: &AccountInfo,
+) -> ProgramResult {
+    if *rent_payer_account.key != pda_stakeholder_account_data.rent_payer {
+        msg!(
+            "Rent payer account != {}",
+            pda_stakeholder_account_data.rent_payer
+        );
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
+
+    close_account(pda_stakeholder_account, rent_payer_account)
+}
+
+// Close channel PDA together with its vault; the vault has to be paid out (empty) by then, so
+// tokens are never stranded behind a closed channel. Rent (all that is left) goes to the opener
+pub fn close_channel<'a>(
+    program_id: &Pubkey,
+    pda_channel_account: &AccountInfo<'a>,
+    pda_channel_account_data: &ChannelState,
+    opener_account: &AccountInfo<'a>,
+    token_accounts: Option<(&AccountInfo<'a>, &AccountInfo<'a>)>, // (vault_pda, token program)
+) -> ProgramResult {
+    if *opener_account.key != pda_channel_account_data.opener {
+        msg!("Opener account != {}", pda_channel_account_data.opener);
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
+
+    if let Some((vault_account, token_program)) = token_accounts {
+        if vault_amount(vault_account)? != 0 {
+            msg!("Channel vault still holds tokens, channel can not be closed");
+            return Err(PaymentChannelError::VaultNotEmpty.into());
+        }
+
+        close_vault(
+            program_id,
+            &pda_channel_account_data.channel_id,
+            vault_account,
+            opener_account,
+            pda_channel_account,
+            token_program,
+        )?;
+    }
+
+    close_account(pda_channel_account, opener_account)?;
+
+    msg!("Channel closed, rent refunded to {}", opener_account.key);
+
+    ChannelEvent::ChannelClosed {
+        channel_id: pda_channel_account_data.channel_id.clone(),
+    }
+    .emit();
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::PaymentChannelError;
+use crate::try_from_slice_unchecked;
+
+// config_pda = ("config"); singleton, created once by the program upgrade authority. Channel id
+// "config" would derive the same PDA, see channel_state::check_channel_id
+pub const CONFIG_SEED: &[u8] = b"config";
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ProgramConfig {
+    // authority for admin-gated instructions (e.g. modify_oracle)
+    pub admin: Pubkey,
+    // proposed by TransferAdmin, Pubkey::default() = none
+    pub pending_admin: Pubkey,
+}
+
+pub fn load_config(
+    program_id: &Pubkey,
+    pda_config_account: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+    let (pda_config, _) = Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+
+    if pda_config != *pda_config_account.key {
+        msg!("pda_config != pda_config_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    match try_from_slice_unchecked::<ProgramConfig>(&pda_config_account.data.borrow()) {
+        Ok(data) => Ok(data),
+        Err(_) => {
+            msg!("Failed to deserialize CONFIG account; program config is not initialized");
+            Err(PaymentChannelError::AccountNotInitialized.into())
+        }
+    }
+}
+
+// Signer must be the admin stored within the program config
+pub fn check_admin(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    pda_config_account: &AccountInfo,
+) -> ProgramResult {
+    let config = load_config(program_id, pda_config_account)?;
+
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *admin.key != config.admin {
+        msg!("Signer {} is not the program admin", admin.key);
+        return Err(PaymentChannelError::Unauthorized.into());
+    }
+
+    Ok(())
+}
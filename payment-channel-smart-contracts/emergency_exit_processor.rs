@@ -0,0 +1,250 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::channel_state::ChannelState;
+use crate::close_account::{close_channel, close_stakeholder};
+use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
+use crate::leave_channel_processor::LEAVE_DISPUTE_WINDOW_SLOTS;
+use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{
+    check_token_account, check_token_program, check_vault, is_token_channel, vault_amount,
+    withdraw_from_vault,
+};
+use crate::try_from_slice_unchecked;
+
+// Min timeout (slots) a channel can be opened with - must not be shorter than the dispute window
+pub const MIN_EMERGENCY_TIMEOUT_SLOTS: u64 = LEAVE_DISPUTE_WINDOW_SLOTS;
+
+// Exit without oracle: once no oracle-signed token was accepted for `emergency_timeout_slots`,
+// stakeholders withdraw their balance as of the latest checkpoint (or as set by a later on-chain
+// action of their own, e.g. deposit / leave). Works while the oracle is suspended.
+pub fn emergency_exit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    channel_id: String,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?;
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_stakeholder_account = next_account_info(account_info_iter)?;
+    let rent_payer_account = next_account_info(account_info_iter)?; // paid the stakeholder PDA rent
+    let opener_account = next_account_info(account_info_iter)?; // paid the channel PDA rent
+                                                                // Only for SPL Token denominated channels: vault_pda, stakeholder token account, token program
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    // Load and check PDA_STAKEHOLDER / STAKEHOLDER ACCOUNT
+    let (pda_stakeholder, _) = Pubkey::find_program_address(
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
+        program_id,
+    );
+
+    if pda_stakeholder != *pda_stakeholder_account.key {
+        msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_stakeholder_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("Wrong Channel ID");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    // Active or with leave pending
+    if pda_stakeholder_account_data.status != 2 && pda_stakeholder_account_data.status != 4 {
+        msg!("msg.sender is not part of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    let emergency_slot = pda_channel_account_data
+        .last_checkpoint_slot
+        .saturating_add(pda_channel_account_data.emergency_timeout_slots);
+
+    let clock = Clock::get()?;
+    if clock.slot < emergency_slot {
+        msg!("Emergency exit not available until slot {}", emergency_slot);
+        return Err(PaymentChannelError::EmergencyExitUnavailable.into());
+    }
+
+    // Pending leave (leave / leave with proof) keeps its dispute window, it can be challenged
+    // until closing_slot
+    if pda_stakeholder_account_data.status == 4
+        && clock.slot < pda_stakeholder_account_data.closing_slot
+    {
+        msg!(
+            "Dispute window still open (until slot {})",
+            pda_stakeholder_account_data.closing_slot
+        );
+        return Err(PaymentChannelError::DisputeWindowOpen.into());
+    }
+
+    // Balance of an active stakeholder is set by every checkpoint, one older than the checkpoint
+    // is not paid out (pending leaves keep the balance set by leave / challenge)
+    if pda_stakeholder_account_data.status == 2
+        && pda_stakeholder_account_data.sequence < pda_channel_account_data.checkpoint_sequence
+    {
+        msg!(
+            "Stakeholder balance (sequence {}) is older than checkpoint sequence {}",
+            pda_stakeholder_account_data.sequence,
+            pda_channel_account_data.checkpoint_sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    let balance = pda_stakeholder_account_data.balance;
+    let token_channel = is_token_channel(&pda_channel_account_data.mint);
+
+    // Last stakeholder takes whatever the channel still holds, so nothing is left behind in the
+    // vault / channel PDA once it is closed
+    let last_stakeholder = pda_channel_account_data.num_of_active_stakeholders == 1;
+
+    let (token_accounts, amount) = if token_channel {
+        let vault_account = next_account_info(account_info_iter)?;
+        let stakeholder_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        check_token_program(token_program)?;
+        check_vault(program_id, pda_channel_account.key, vault_account)?;
+        check_token_account(
+            stakeholder_token_account,
+            &pda_channel_account_data.mint,
+            msg_sender.key,
+        )?;
+
+        let channel_funds = vault_amount(vault_account)?;
+        if channel_funds < balance {
+            msg!("Channel vault does not hold enough tokens");
+            return Err(PaymentChannelError::InsufficientFunds.into());
+        }
+
+        let amount = if last_stakeholder {
+            channel_funds
+        } else {
+            balance
+        };
+
+        msg!("amount (tokens) to be transfered: {}", amount);
+        withdraw_from_vault(
+            program_id,
+            &channel_id,
+            vault_account,
+            stakeholder_token_account,
+            pda_channel_account,
+            token_program,
+            amount,
+        )?;
+
+        (Some((vault_account, token_program)), amount)
+    } else {
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(pda_channel_account.data_len());
+        let channel_funds = pda_channel_account
+            .lamports()
+            .saturating_sub(rent_exempt_lamports);
+
+        if channel_funds < balance {
+            msg!("Channel does not hold enough lamports");
+            return Err(PaymentChannelError::InsufficientFunds.into());
+        }
+
+        let amount = if last_stakeholder {
+            channel_funds
+        } else {
+            balance
+        };
+
+        (None, amount)
+    };
+
+    // ASSIGN VALUES
+    pda_channel_account_data.num_of_active_stakeholders -= 1;
+    pda_channel_account_data.total_deposits = pda_channel_account_data
+        .total_deposits
+        .saturating_sub(balance);
+
+    if pda_channel_account_data.num_of_active_stakeholders == 0 {
+        pda_channel_account_data.current_status = 2; // 1 = OPENED // 2 = CLOSED
+    }
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    if !token_channel {
+        msg!("emergency amount to be transfered: {}", amount);
+
+        **pda_channel_account.try_borrow_mut_lamports()? -= amount;
+        **msg_sender.try_borrow_mut_lamports()? += amount;
+    }
+
+    ChannelEvent::StakeholderLeft {
+        channel_id: channel_id.clone(),
+        stakeholder: *msg_sender.key,
+        amount,
+    }
+    .emit();
+
+    // Stakeholder is out of the channel (status 3 = inactive) - PDA is closed, rent refunded
+    close_stakeholder(
+        pda_stakeholder_account,
+        &pda_stakeholder_account_data,
+        rent_payer_account,
+    )?;
+
+    // Last stakeholder left - channel PDA is closed too
+    if pda_channel_account_data.current_status == 2 {
+        close_channel(
+            program_id,
+            pda_channel_account,
+            &pda_channel_account_data,
+            opener_account,
+            token_accounts,
+        )?;
+    }
+
+    msg!("Stakeholder {} exited channel (emergency)", msg_sender.key);
+
+    Ok(())
+}
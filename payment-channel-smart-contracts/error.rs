@@ -1,10 +1,98 @@
 use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+// Returned as ProgramError::Custom(code); codes are stable - new variants are only appended.
+// Code 0 keeps its meaning from the first version of the program (generic error)
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
 pub enum PaymentChannelError {
     #[error("Payment Channel Error")]
-    Error,
+    Error = 0,
+    #[error("Invalid seeds: account is not the expected PDA")]
+    InvalidSeeds = 1,
+    #[error("Failed to decode token")]
+    InvalidTokenEncoding = 2,
+    #[error("Account is not initialized or holds unexpected data")]
+    AccountNotInitialized = 3,
+    #[error("Account already exists")]
+    AccountAlreadyInitialized = 4,
+    #[error("Wrong action defined within token")]
+    WrongAction = 5,
+    #[error("Wrong prev_state defined within token")]
+    WrongPrevState = 6,
+    #[error("Channel ID does not match")]
+    ChannelIdMismatch = 7,
+    #[error("Channel is not opened")]
+    ChannelNotOpened = 8,
+    #[error("Stakeholder status does not allow this action")]
+    InvalidStakeholderStatus = 9,
+    #[error("Sender of the transaction is not the sender defined in token")]
+    SenderMismatch = 10,
+    #[error("Invalid amount")]
+    InvalidAmount = 11,
+    #[error("Token sequence is stale")]
+    StaleSequence = 12,
+    #[error("Sender signature verification failed")]
+    InvalidSenderSignature = 13,
+    #[error("Oracle signature verification failed")]
+    InvalidOracleSignature = 14,
+    #[error("Not enough oracle signatures")]
+    NotEnoughOracleSignatures = 15,
+    #[error("Invalid oracle set")]
+    InvalidOracleSet = 16,
+    #[error("Oracle is not an oracle of the channel")]
+    OracleNotInChannel = 17,
+    #[error("Oracle is suspended")]
+    OracleInactive = 18,
+    #[error("Oracle key version is not accepted")]
+    OracleKeyNotAccepted = 19,
+    #[error("Insufficient funds")]
+    InsufficientFunds = 20,
+    #[error("Arithmetic overflow")]
+    ArithmeticOverflow = 21,
+    #[error("Signer is not authorized")]
+    Unauthorized = 22,
+    #[error("Account is not owned by the program")]
+    InvalidOwner = 23,
+    #[error("Dispute window still open")]
+    DisputeWindowOpen = 24,
+    #[error("Dispute window is over")]
+    DisputeWindowClosed = 25,
+    #[error("Emergency exit not available yet")]
+    EmergencyExitUnavailable = 26,
+    #[error("Invalid token account, mint, vault or token program")]
+    InvalidTokenAccount = 27,
+    #[error("Malformed Ed25519Program instruction")]
+    InvalidEd25519Instruction = 28,
+    #[error("No matching Ed25519Program signature found")]
+    SignatureNotFound = 29,
+    #[error("Invalid fraud proof")]
+    InvalidFraudProof = 30,
+    #[error("Oracle bond already slashed")]
+    BondAlreadySlashed = 31,
+    #[error("Token does not match the channel checkpoint")]
+    CheckpointMismatch = 32,
+    #[error("Invalid Merkle proof")]
+    InvalidMerkleProof = 33,
+    #[error("Balance proof already claimed")]
+    ProofAlreadyClaimed = 34,
+    #[error("Token state does not match the provided accounts")]
+    StateMismatch = 35,
+    #[error("Invalid argument")]
+    InvalidArgument = 36,
+    #[error("Unknown instruction tag")]
+    UnknownInstruction = 37,
+    #[error("Instruction data is shorter than declared")]
+    InstructionTooShort = 38,
+    #[error("Instruction data is longer than declared")]
+    InstructionLengthMismatch = 39,
+    #[error("Malformed instruction payload")]
+    InvalidInstructionPayload = 40,
+    #[error("Channel vault still holds tokens")]
+    VaultNotEmpty = 41,
+    #[error("Token was issued for another incarnation of the channel")]
+    ChannelNonceMismatch = 42,
+    #[error("Channel ID starts with a reserved PDA seed")]
+    ReservedChannelId = 43,
 }
 
 impl From<PaymentChannelError> for ProgramError {
@@ -12,3 +100,102 @@ impl From<PaymentChannelError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+// Decode ProgramError::Custom(code) (e.g. on the client side)
+impl TryFrom<u32> for PaymentChannelError {
+    type Error = u32;
+
+    fn try_from(code: u32) -> Result<Self, u32> {
+        use PaymentChannelError::*;
+
+        const ERRORS: [PaymentChannelError; 44] = [
+            Error,
+            InvalidSeeds,
+            InvalidTokenEncoding,
+            AccountNotInitialized,
+            AccountAlreadyInitialized,
+            WrongAction,
+            WrongPrevState,
+            ChannelIdMismatch,
+            ChannelNotOpened,
+            InvalidStakeholderStatus,
+            SenderMismatch,
+            InvalidAmount,
+            StaleSequence,
+            InvalidSenderSignature,
+            InvalidOracleSignature,
+            NotEnoughOracleSignatures,
+            InvalidOracleSet,
+            OracleNotInChannel,
+            OracleInactive,
+            OracleKeyNotAccepted,
+            InsufficientFunds,
+            ArithmeticOverflow,
+            Unauthorized,
+            InvalidOwner,
+            DisputeWindowOpen,
+            DisputeWindowClosed,
+            EmergencyExitUnavailable,
+            InvalidTokenAccount,
+            InvalidEd25519Instruction,
+            SignatureNotFound,
+            InvalidFraudProof,
+            BondAlreadySlashed,
+            CheckpointMismatch,
+            InvalidMerkleProof,
+            ProofAlreadyClaimed,
+            StateMismatch,
+            InvalidArgument,
+            UnknownInstruction,
+            InstructionTooShort,
+            InstructionLengthMismatch,
+            InvalidInstructionPayload,
+            VaultNotEmpty,
+            ChannelNonceMismatch,
+            ReservedChannelId,
+        ];
+
+        ERRORS
+            .iter()
+            .find(|e| **e as u32 == code)
+            .copied()
+            .ok_or(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Error names of the JS clients, index = code
+    fn client_errors() -> Vec<String> {
+        let source = include_str!("../payment-channel-client/utils/program-errors-client.js");
+        let table = &source[source.find("program_errors = [").unwrap()..];
+        let table = &table[..table.find("];").unwrap()];
+
+        table
+            .lines()
+            .skip(1)
+            .map(|line| line.trim().trim_end_matches(',').trim_matches('\''))
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn every_code_roundtrips_and_matches_the_client_table() {
+        let client_errors = client_errors();
+
+        for (code, name) in client_errors.iter().enumerate() {
+            let error = PaymentChannelError::try_from(code as u32).unwrap();
+
+            assert_eq!(error as u32, code as u32);
+            assert_eq!(ProgramError::from(error), ProgramError::Custom(code as u32));
+            assert_eq!(&format!("{:?}", error), name);
+        }
+
+        // Every code of the program is in the table, the first code after it is unknown
+        let unknown = client_errors.len() as u32;
+        assert_eq!(PaymentChannelError::try_from(unknown), Err(unknown));
+    }
+}
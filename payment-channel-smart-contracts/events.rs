@@ -0,0 +1,162 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+// Version byte put in front of every event; bumped on incompatible changes of ChannelEvent
+pub const EVENT_VERSION: u8 = 1;
+
+// Emitted through sol_log_data ("Program data: <base64>" in the transaction logs) as
+// EVENT_VERSION followed by the Borsh encoded event. Variants are only appended.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum ChannelEvent {
+    ChannelOpened {
+        channel_id: String,
+        opener: Pubkey,
+        mint: Pubkey,
+        balance: u64,
+        sequence: u64,
+    },
+    StakeholderInvited {
+        channel_id: String,
+        inviter: Pubkey,
+        invitee: Pubkey,
+    },
+    StakeholderJoined {
+        channel_id: String,
+        stakeholder: Pubkey,
+        balance: u64,
+        sequence: u64,
+        join_token: Vec<u8>, // join token as submitted (stored off-chain by the oracle)
+    },
+    StakeholderLeft {
+        channel_id: String,
+        stakeholder: Pubkey,
+        amount: u64, // paid out
+    },
+    ChannelClosed {
+        channel_id: String,
+    },
+    OracleModified {
+        oracle_address: Pubkey,
+        oracle_status: bool,
+        fee: u64,
+    },
+    Deposited {
+        channel_id: String,
+        stakeholder: Pubkey,
+        amount: u64,
+        sequence: u64,
+    },
+    Withdrawn {
+        channel_id: String,
+        stakeholder: Pubkey,
+        amount: u64,
+        sequence: u64,
+    },
+}
+
+impl ChannelEvent {
+    // EVENT_VERSION followed by the Borsh encoded event
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![EVENT_VERSION];
+        // Serializing into a Vec cannot fail
+        self.serialize(&mut data).unwrap();
+
+        data
+    }
+
+    pub fn emit(&self) {
+        sol_log_data(&[&self.encode()]);
+    }
+
+    // Decode the (base64 decoded) data of a "Program data:" log line; None for other data
+    // or events of an unknown version
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (&version, event) = data.split_first()?;
+
+        if version != EVENT_VERSION {
+            return None;
+        }
+
+        Self::try_from_slice(event).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_variant() -> Vec<ChannelEvent> {
+        let channel_id = "channel".to_string();
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        vec![
+            ChannelEvent::ChannelOpened {
+                channel_id: channel_id.clone(),
+                opener: alice,
+                mint: Pubkey::default(),
+                balance: 100,
+                sequence: 1,
+            },
+            ChannelEvent::StakeholderInvited {
+                channel_id: channel_id.clone(),
+                inviter: alice,
+                invitee: bob,
+            },
+            ChannelEvent::StakeholderJoined {
+                channel_id: channel_id.clone(),
+                stakeholder: bob,
+                balance: 50,
+                sequence: 2,
+                join_token: vec![1, 2, 3],
+            },
+            ChannelEvent::StakeholderLeft {
+                channel_id: channel_id.clone(),
+                stakeholder: bob,
+                amount: 50,
+            },
+            ChannelEvent::ChannelClosed {
+                channel_id: channel_id.clone(),
+            },
+            ChannelEvent::OracleModified {
+                oracle_address: alice,
+                oracle_status: true,
+                fee: 5,
+            },
+            ChannelEvent::Deposited {
+                channel_id: channel_id.clone(),
+                stakeholder: alice,
+                amount: 10,
+                sequence: 3,
+            },
+            ChannelEvent::Withdrawn {
+                channel_id,
+                stakeholder: alice,
+                amount: 10,
+                sequence: 4,
+            },
+        ]
+    }
+
+    #[test]
+    fn every_variant_roundtrips() {
+        for (variant, event) in every_variant().into_iter().enumerate() {
+            let data = event.encode();
+
+            // [version, variant, ...] is what off-chain listeners match on
+            assert_eq!(data[0], EVENT_VERSION);
+            assert_eq!(data[1] as usize, variant);
+            assert_eq!(ChannelEvent::decode(&data), Some(event));
+        }
+    }
+
+    #[test]
+    fn unknown_version_or_truncated_data_is_not_decoded() {
+        let mut data = every_variant()[0].encode();
+
+        assert_eq!(ChannelEvent::decode(&[]), None);
+        assert_eq!(ChannelEvent::decode(&data[..data.len() - 1]), None);
+
+        data[0] = EVENT_VERSION + 1;
+        assert_eq!(ChannelEvent::decode(&data), None);
+    }
+}
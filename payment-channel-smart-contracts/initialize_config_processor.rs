@@ -0,0 +1,120 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::config_state::{ProgramConfig, CONFIG_SEED};
+use crate::error::PaymentChannelError;
+use crate::try_from_slice_unchecked;
+
+// UpgradeableLoaderState::ProgramData = u32 tag (3) + u64 slot + Option<Pubkey> upgrade authority
+const PROGRAM_DATA_TAG: u32 = 3;
+const PROGRAM_DATA_AUTHORITY_OFFSET: usize = 4 + 8;
+
+pub fn initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    admin: Pubkey,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let upgrade_authority = next_account_info(account_info_iter)?; // msg.sender
+    let pda_config_account = next_account_info(account_info_iter)?;
+    let program_data_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !upgrade_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Only the upgrade authority of this program can initialize the config
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    if program_data != *program_data_account.key
+        || *program_data_account.owner != bpf_loader_upgradeable::id()
+    {
+        msg!("Wrong ProgramData account provided");
+        return Err(PaymentChannelError::Unauthorized.into());
+    }
+
+    let program_upgrade_authority = {
+        let data = program_data_account.data.borrow();
+        let authority = PROGRAM_DATA_AUTHORITY_OFFSET + 1;
+
+        if data.len() < authority + 32
+            || data[..4] != PROGRAM_DATA_TAG.to_le_bytes()
+            || data[PROGRAM_DATA_AUTHORITY_OFFSET] != 1
+        {
+            msg!("Program is not upgradeable or ProgramData account is malformed");
+            return Err(PaymentChannelError::Unauthorized.into());
+        }
+
+        Pubkey::try_from(&data[authority..authority + 32]).unwrap()
+    };
+
+    if *upgrade_authority.key != program_upgrade_authority {
+        msg!("Signer is not the program upgrade authority");
+        return Err(PaymentChannelError::Unauthorized.into());
+    }
+
+    let (pda_config, pda_config_bump_seed) =
+        Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+
+    if pda_config != *pda_config_account.key {
+        msg!("pda_config != pda_config_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    // Calculate account size required
+    let pda_config_account_len: usize = 32 + 32; // admin + pending_admin
+
+    // Calculate rent required
+    let rent = Rent::get()?;
+    let rent_lamports = rent.minimum_balance(pda_config_account_len);
+
+    match invoke_signed(
+        &system_instruction::create_account(
+            upgrade_authority.key,
+            pda_config_account.key,
+            rent_lamports,
+            pda_config_account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            upgrade_authority.clone(),
+            pda_config_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[CONFIG_SEED, &[pda_config_bump_seed]]], // config pda seed = "config"
+    ) {
+        Ok(()) => (),
+        Err(_) => {
+            msg!("Program config already initialized!");
+            return Err(PaymentChannelError::AccountAlreadyInitialized.into());
+        }
+    }
+
+    let mut pda_config_account_data =
+        try_from_slice_unchecked::<ProgramConfig>(&pda_config_account.data.borrow()).unwrap();
+
+    // ASSIGN VALUES
+    pda_config_account_data.admin = admin;
+    pda_config_account_data.pending_admin = Pubkey::default();
+
+    pda_config_account_data.serialize(&mut &mut pda_config_account.data.borrow_mut()[..])?;
+
+    msg!("Program config initialized, admin: {}", admin);
+
+    Ok(())
+}
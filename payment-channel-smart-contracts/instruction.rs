@@ -1,73 +1,652 @@
-use borsh::BorshDeserialize;
+use borsh::maybestd::io::{Error, ErrorKind, Read, Result as IoResult, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
+use crate::error::PaymentChannelError;
+
+// Wire format: 1 byte tag followed by the Borsh encoded fields of the variant, in declaration
+// order. Strings, tokens (Vec<u8>) and proofs carry a u32 length prefix which may not exceed the
+// remaining data. `unpack` and BorshDeserialize share one decoder (InstructionReader).
+// Tags are stable (4 is unused) - new instructions are only appended.
+#[derive(Debug, Clone, PartialEq)]
 pub enum ChannelInstruction {
-    ModifyOracle { oracle_address: Pubkey },
-    OpenChannel { open_token_encoded: Vec<u8> },
-    JoinChannel { join_token_encoded: Vec<u8> },
-    LeaveChannel { leave_token_encoded: Vec<u8> },
-    InviteToChannel { channel_id: String, invitee: Pubkey },
+    ModifyOracle {
+        oracle_address: Pubkey,
+        oracle_status: bool,
+        name: String,
+        endpoint_hash: [u8; 32],
+        fee: u64,
+    },
+    OpenChannel {
+        open_token_encoded: Vec<u8>,
+    },
+    JoinChannel {
+        join_token_encoded: Vec<u8>,
+    },
+    LeaveChannel {
+        leave_token_encoded: Vec<u8>,
+    },
+    InviteToChannel {
+        channel_id: String,
+        invitee: Pubkey,
+    },
+    ChallengeLeave {
+        challenge_token_encoded: Vec<u8>,
+    },
+    FinalizeLeave {
+        channel_id: String,
+    },
+    SettleChannel {
+        settle_token_encoded: Vec<u8>,
+    },
+    Deposit {
+        deposit_token_encoded: Vec<u8>,
+    },
+    Withdraw {
+        withdraw_token_encoded: Vec<u8>,
+    },
+    InitializeConfig {
+        admin: Pubkey,
+    },
+    TransferAdmin {
+        new_admin: Pubkey,
+    },
+    AcceptAdmin,
+    RotateOracleKey {
+        new_key: Pubkey,
+        handover_slots: u64,
+    },
+    ProveOracleFraud {
+        first_token_encoded: Vec<u8>,
+        second_token_encoded: Vec<u8>, // empty = over-sum proof
+    },
+    EmergencyExit {
+        channel_id: String,
+    },
+    Checkpoint {
+        checkpoint_token_encoded: Vec<u8>,
+        cid_token_encoded: Vec<u8>, // oracle-signed IPFS path of the checkpointed state
+    },
+    CheckpointRoot {
+        root_token_encoded: Vec<u8>,
+    },
+    LeaveWithProof {
+        channel_id: String,
+        balance: u64,
+        leaf_index: u64,
+        proof: Vec<[u8; 32]>, // sibling hashes, leaf to root
+    },
+    RevokeInvite {
+        channel_id: String,
+        invitee: Pubkey,
+    },
+    DeclineInvite {
+        channel_id: String,
+    },
+    MigrateChannel {
+        channel_id: String,
+    },
 }
 
-#[derive(BorshDeserialize)]
-struct ModifyOraclePayload {
-    oracle_address: Pubkey,
+impl ChannelInstruction {
+    // Tag (first byte of the instruction data) of the variant
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::OpenChannel { .. } => 1,
+            Self::JoinChannel { .. } => 2,
+            Self::LeaveChannel { .. } => 3,
+            Self::ModifyOracle { .. } => 5,
+            Self::InviteToChannel { .. } => 6,
+            Self::ChallengeLeave { .. } => 7,
+            Self::FinalizeLeave { .. } => 8,
+            Self::SettleChannel { .. } => 9,
+            Self::Deposit { .. } => 10,
+            Self::Withdraw { .. } => 11,
+            Self::InitializeConfig { .. } => 12,
+            Self::TransferAdmin { .. } => 13,
+            Self::AcceptAdmin => 14,
+            Self::RotateOracleKey { .. } => 15,
+            Self::ProveOracleFraud { .. } => 16,
+            Self::EmergencyExit { .. } => 17,
+            Self::Checkpoint { .. } => 18,
+            Self::CheckpointRoot { .. } => 19,
+            Self::LeaveWithProof { .. } => 20,
+            Self::RevokeInvite { .. } => 21,
+            Self::DeclineInvite { .. } => 22,
+            Self::MigrateChannel { .. } => 23,
+        }
+    }
+
+    // Pack instruction into instruction data (client side)
+    pub fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+        self.try_to_vec()
+            .map_err(|_| PaymentChannelError::InvalidInstructionPayload.into())
+    }
+
+    // Unpack inbound buffer to associated Instruction
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let mut reader = InstructionReader { data: input };
+        let instruction = reader.instruction()?;
+
+        // Bytes after the last field (e.g. token shorter than the data that follows the length prefix)
+        if !reader.data.is_empty() {
+            return Err(PaymentChannelError::InstructionLengthMismatch.into());
+        }
+
+        Ok(instruction)
+    }
 }
 
-#[derive(BorshDeserialize)]
-struct InviteToChannelPayload {
-    channel_id: String,
-    invitee: Pubkey,
+// Reads the Borsh encoded fields off the instruction data. Every length prefix is checked
+// against the remaining data before anything is allocated.
+struct InstructionReader<'a> {
+    data: &'a [u8],
 }
 
-#[derive(BorshDeserialize)]
-struct JoinChannelPayload {
-    channel_id: String,
+impl<'a> InstructionReader<'a> {
+    // Tag and fields of one instruction
+    fn instruction(&mut self) -> Result<ChannelInstruction, PaymentChannelError> {
+        let instruction = match self.u8()? {
+            1 => ChannelInstruction::OpenChannel {
+                open_token_encoded: self.bytes()?,
+            },
+            2 => ChannelInstruction::JoinChannel {
+                join_token_encoded: self.bytes()?,
+            },
+            3 => ChannelInstruction::LeaveChannel {
+                leave_token_encoded: self.bytes()?,
+            },
+            5 => ChannelInstruction::ModifyOracle {
+                oracle_address: self.pubkey()?,
+                oracle_status: self.bool()?,
+                name: self.string()?,
+                endpoint_hash: self.hash()?,
+                fee: self.u64()?,
+            },
+            6 => ChannelInstruction::InviteToChannel {
+                channel_id: self.string()?,
+                invitee: self.pubkey()?,
+            },
+            7 => ChannelInstruction::ChallengeLeave {
+                challenge_token_encoded: self.bytes()?,
+            },
+            8 => ChannelInstruction::FinalizeLeave {
+                channel_id: self.string()?,
+            },
+            9 => ChannelInstruction::SettleChannel {
+                settle_token_encoded: self.bytes()?,
+            },
+            10 => ChannelInstruction::Deposit {
+                deposit_token_encoded: self.bytes()?,
+            },
+            11 => ChannelInstruction::Withdraw {
+                withdraw_token_encoded: self.bytes()?,
+            },
+            12 => ChannelInstruction::InitializeConfig {
+                admin: self.pubkey()?,
+            },
+            13 => ChannelInstruction::TransferAdmin {
+                new_admin: self.pubkey()?,
+            },
+            14 => ChannelInstruction::AcceptAdmin,
+            15 => ChannelInstruction::RotateOracleKey {
+                new_key: self.pubkey()?,
+                handover_slots: self.u64()?,
+            },
+            16 => ChannelInstruction::ProveOracleFraud {
+                first_token_encoded: self.bytes()?,
+                second_token_encoded: self.bytes()?,
+            },
+            17 => ChannelInstruction::EmergencyExit {
+                channel_id: self.string()?,
+            },
+            18 => ChannelInstruction::Checkpoint {
+                checkpoint_token_encoded: self.bytes()?,
+                cid_token_encoded: self.bytes()?,
+            },
+            19 => ChannelInstruction::CheckpointRoot {
+                root_token_encoded: self.bytes()?,
+            },
+            20 => ChannelInstruction::LeaveWithProof {
+                channel_id: self.string()?,
+                balance: self.u64()?,
+                leaf_index: self.u64()?,
+                proof: self.hashes()?,
+            },
+            21 => ChannelInstruction::RevokeInvite {
+                channel_id: self.string()?,
+                invitee: self.pubkey()?,
+            },
+            22 => ChannelInstruction::DeclineInvite {
+                channel_id: self.string()?,
+            },
+            23 => ChannelInstruction::MigrateChannel {
+                channel_id: self.string()?,
+            },
+            _ => return Err(PaymentChannelError::UnknownInstruction),
+        };
+
+        Ok(instruction)
+    }
+
+    // Data ended before the tag, a length prefix or a field (incl. declared length)
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PaymentChannelError> {
+        if len > self.data.len() {
+            return Err(PaymentChannelError::InstructionTooShort);
+        }
+
+        let (field, rest) = self.data.split_at(len);
+        self.data = rest;
+
+        Ok(field)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], PaymentChannelError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, PaymentChannelError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, PaymentChannelError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(PaymentChannelError::InvalidInstructionPayload),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, PaymentChannelError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, PaymentChannelError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, PaymentChannelError> {
+        Ok(Pubkey::new_from_array(self.array()?))
+    }
+
+    fn hash(&mut self) -> Result<[u8; 32], PaymentChannelError> {
+        self.array()
+    }
+
+    // Vec<u8> (tokens): u32 length prefix followed by the bytes
+    fn bytes(&mut self) -> Result<Vec<u8>, PaymentChannelError> {
+        let len = self.u32()? as usize;
+
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, PaymentChannelError> {
+        String::from_utf8(self.bytes()?).map_err(|_| PaymentChannelError::InvalidInstructionPayload)
+    }
+
+    // Vec<[u8; 32]>: u32 number of hashes followed by the hashes
+    fn hashes(&mut self) -> Result<Vec<[u8; 32]>, PaymentChannelError> {
+        let len = self.u32()? as usize;
+        let data = self.take(
+            len.checked_mul(32)
+                .ok_or(PaymentChannelError::InstructionTooShort)?,
+        )?;
+
+        Ok(data
+            .chunks_exact(32)
+            .map(|hash| {
+                let mut array = [0u8; 32];
+                array.copy_from_slice(hash);
+                array
+            })
+            .collect())
+    }
 }
 
-impl ChannelInstruction {
-    // Unpack inbound buffer to associated Instruction
-    // The expected format for input is a Borsh serialized vector
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        // Split the first byte of data
-        let (&variant, rest) = input
-            .split_first()
-            .ok_or(ProgramError::InvalidInstructionData)?;
-        // `try_from_slice` is one of the implementations from the BorshDeserialization trait
-        // Deserializes instruction byte data into the payload struct
-        //let payload = OpenChannelPayload::try_from_slice(rest).unwrap();
-        // Match the first byte and return the AddMovieReview struct
-
-        Ok(match variant {
-            1 => {
-                // let payload = OpenPayload::try_from_slice(rest).unwrap();
-                Self::OpenChannel {
-                    open_token_encoded: rest.to_vec().split_off(4), // bytes to slice // split_off = first 4 elements represent lenght
-                }
+impl BorshSerialize for ChannelInstruction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        self.tag().serialize(writer)?;
+
+        match self {
+            Self::OpenChannel { open_token_encoded } => open_token_encoded.serialize(writer),
+            Self::JoinChannel { join_token_encoded } => join_token_encoded.serialize(writer),
+            Self::LeaveChannel {
+                leave_token_encoded,
+            } => leave_token_encoded.serialize(writer),
+            Self::ModifyOracle {
+                oracle_address,
+                oracle_status,
+                name,
+                endpoint_hash,
+                fee,
+            } => {
+                oracle_address.serialize(writer)?;
+                oracle_status.serialize(writer)?;
+                name.serialize(writer)?;
+                endpoint_hash.serialize(writer)?;
+                fee.serialize(writer)
             }
-            2 => {
-                // let payload = OpenPayload::try_from_slice(rest).unwrap();
-                Self::JoinChannel {
-                    join_token_encoded: rest.to_vec().split_off(4), // bytes to slice // split_off = first 4 elements represent lenght
-                }
+            Self::InviteToChannel {
+                channel_id,
+                invitee,
+            } => {
+                channel_id.serialize(writer)?;
+                invitee.serialize(writer)
             }
-            3 => Self::LeaveChannel {
-                leave_token_encoded: rest.to_vec().split_off(4),
-            },
-            5 => {
-                let payload = ModifyOraclePayload::try_from_slice(rest).unwrap();
-                Self::ModifyOracle {
-                    oracle_address: payload.oracle_address,
-                }
+            Self::ChallengeLeave {
+                challenge_token_encoded,
+            } => challenge_token_encoded.serialize(writer),
+            Self::FinalizeLeave { channel_id } => channel_id.serialize(writer),
+            Self::SettleChannel {
+                settle_token_encoded,
+            } => settle_token_encoded.serialize(writer),
+            Self::Deposit {
+                deposit_token_encoded,
+            } => deposit_token_encoded.serialize(writer),
+            Self::Withdraw {
+                withdraw_token_encoded,
+            } => withdraw_token_encoded.serialize(writer),
+            Self::InitializeConfig { admin } => admin.serialize(writer),
+            Self::TransferAdmin { new_admin } => new_admin.serialize(writer),
+            Self::AcceptAdmin => Ok(()),
+            Self::RotateOracleKey {
+                new_key,
+                handover_slots,
+            } => {
+                new_key.serialize(writer)?;
+                handover_slots.serialize(writer)
             }
-            6 => {
-                let payload = InviteToChannelPayload::try_from_slice(rest).unwrap();
-                Self::InviteToChannel {
-                    channel_id: payload.channel_id,
-                    invitee: payload.invitee,
-                }
+            Self::ProveOracleFraud {
+                first_token_encoded,
+                second_token_encoded,
+            } => {
+                first_token_encoded.serialize(writer)?;
+                second_token_encoded.serialize(writer)
             }
-            _ => return Err(ProgramError::InvalidInstructionData),
-        })
+            Self::EmergencyExit { channel_id } => channel_id.serialize(writer),
+            Self::Checkpoint {
+                checkpoint_token_encoded,
+                cid_token_encoded,
+            } => {
+                checkpoint_token_encoded.serialize(writer)?;
+                cid_token_encoded.serialize(writer)
+            }
+            Self::CheckpointRoot { root_token_encoded } => root_token_encoded.serialize(writer),
+            Self::LeaveWithProof {
+                channel_id,
+                balance,
+                leaf_index,
+                proof,
+            } => {
+                channel_id.serialize(writer)?;
+                balance.serialize(writer)?;
+                leaf_index.serialize(writer)?;
+                proof.serialize(writer)
+            }
+            Self::RevokeInvite {
+                channel_id,
+                invitee,
+            } => {
+                channel_id.serialize(writer)?;
+                invitee.serialize(writer)
+            }
+            Self::DeclineInvite { channel_id } => channel_id.serialize(writer),
+            Self::MigrateChannel { channel_id } => channel_id.serialize(writer),
+        }
+    }
+}
+
+// Same decoder as `unpack`: the slice is advanced past the instruction, `try_from_slice` fails on
+// bytes after it
+impl BorshDeserialize for ChannelInstruction {
+    fn deserialize(buf: &mut &[u8]) -> IoResult<Self> {
+        let mut reader = InstructionReader { data: buf };
+        let instruction = reader
+            .instruction()
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+        *buf = reader.data;
+
+        Ok(instruction)
+    }
+
+    // Instruction data is the whole input of the reader
+    fn deserialize_reader<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::try_from_slice(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_instruction() -> Vec<ChannelInstruction> {
+        let (key, channel_id, token) = (Pubkey::new_unique(), "channel".to_string(), vec![1, 2, 3]);
+
+        vec![
+            ChannelInstruction::OpenChannel {
+                open_token_encoded: token.clone(),
+            },
+            ChannelInstruction::JoinChannel {
+                join_token_encoded: token.clone(),
+            },
+            ChannelInstruction::LeaveChannel {
+                leave_token_encoded: token.clone(),
+            },
+            ChannelInstruction::ModifyOracle {
+                oracle_address: key,
+                oracle_status: true,
+                name: "oracle".to_string(),
+                endpoint_hash: [7; 32],
+                fee: 5,
+            },
+            ChannelInstruction::InviteToChannel {
+                channel_id: channel_id.clone(),
+                invitee: key,
+            },
+            ChannelInstruction::ChallengeLeave {
+                challenge_token_encoded: token.clone(),
+            },
+            ChannelInstruction::FinalizeLeave {
+                channel_id: channel_id.clone(),
+            },
+            ChannelInstruction::SettleChannel {
+                settle_token_encoded: token.clone(),
+            },
+            ChannelInstruction::Deposit {
+                deposit_token_encoded: token.clone(),
+            },
+            ChannelInstruction::Withdraw {
+                withdraw_token_encoded: token.clone(),
+            },
+            ChannelInstruction::InitializeConfig { admin: key },
+            ChannelInstruction::TransferAdmin { new_admin: key },
+            ChannelInstruction::AcceptAdmin,
+            ChannelInstruction::RotateOracleKey {
+                new_key: key,
+                handover_slots: 100,
+            },
+            ChannelInstruction::ProveOracleFraud {
+                first_token_encoded: token.clone(),
+                second_token_encoded: vec![],
+            },
+            ChannelInstruction::EmergencyExit {
+                channel_id: channel_id.clone(),
+            },
+            ChannelInstruction::Checkpoint {
+                checkpoint_token_encoded: token.clone(),
+                cid_token_encoded: vec![4, 5],
+            },
+            ChannelInstruction::CheckpointRoot {
+                root_token_encoded: token,
+            },
+            ChannelInstruction::LeaveWithProof {
+                channel_id: channel_id.clone(),
+                balance: 10,
+                leaf_index: 3,
+                proof: vec![[1; 32], [2; 32]],
+            },
+            ChannelInstruction::RevokeInvite {
+                channel_id: channel_id.clone(),
+                invitee: key,
+            },
+            ChannelInstruction::DeclineInvite {
+                channel_id: channel_id.clone(),
+            },
+            ChannelInstruction::MigrateChannel { channel_id },
+        ]
+    }
+
+    fn error(error: PaymentChannelError) -> Result<ChannelInstruction, ProgramError> {
+        Err(error.into())
+    }
+
+    #[test]
+    fn every_tag_roundtrips() {
+        let instructions = every_instruction();
+        let tags: Vec<u8> = instructions.iter().map(|i| i.tag()).collect();
+        assert_eq!(
+            tags,
+            [&[1, 2, 3][..], &(5..=23).collect::<Vec<u8>>()].concat()
+        );
+
+        for instruction in instructions {
+            let data = instruction.pack().unwrap();
+
+            assert_eq!(data[0], instruction.tag());
+            assert_eq!(ChannelInstruction::unpack(&data), Ok(instruction));
+        }
+    }
+
+    #[test]
+    fn borsh_and_unpack_decode_alike() {
+        for instruction in every_instruction() {
+            let data = instruction.try_to_vec().unwrap();
+
+            assert_eq!(
+                ChannelInstruction::try_from_slice(&data).unwrap(),
+                instruction
+            );
+            assert_eq!(
+                ChannelInstruction::deserialize_reader(&mut &data[..]).unwrap(),
+                instruction
+            );
+            assert_eq!(ChannelInstruction::unpack(&data), Ok(instruction));
+
+            // Both reject truncated data and bytes after the instruction
+            let mut trailing = data.clone();
+            trailing.push(0);
+            assert!(ChannelInstruction::try_from_slice(&trailing).is_err());
+            assert!(ChannelInstruction::unpack(&trailing).is_err());
+
+            for len in 0..data.len() {
+                assert!(ChannelInstruction::try_from_slice(&data[..len]).is_err());
+                assert!(ChannelInstruction::unpack(&data[..len]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_data_is_too_short() {
+        assert_eq!(
+            ChannelInstruction::unpack(&[]),
+            error(PaymentChannelError::InstructionTooShort)
+        );
+
+        for instruction in every_instruction() {
+            let data = instruction.pack().unwrap();
+
+            // Cut anywhere after the tag: inside a length prefix, a fixed size field or a token
+            for len in 1..data.len() {
+                assert_eq!(
+                    ChannelInstruction::unpack(&data[..len]),
+                    error(PaymentChannelError::InstructionTooShort),
+                    "{:?} cut at {}",
+                    instruction,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn trailing_data_is_length_mismatch() {
+        for instruction in every_instruction() {
+            let mut data = instruction.pack().unwrap();
+            data.push(0);
+
+            assert_eq!(
+                ChannelInstruction::unpack(&data),
+                error(PaymentChannelError::InstructionLengthMismatch),
+                "{:?}",
+                instruction
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_tag_is_rejected() {
+        for tag in [0, 4, 24, u8::MAX] {
+            assert_eq!(
+                ChannelInstruction::unpack(&[tag, 0, 0, 0, 0]),
+                error(PaymentChannelError::UnknownInstruction)
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_declared_length_is_rejected() {
+        // Token declared with u32::MAX bytes, followed by three
+        let mut data = vec![1];
+        data.extend(u32::MAX.to_le_bytes());
+        data.extend([1, 2, 3]);
+
+        assert_eq!(
+            ChannelInstruction::unpack(&data),
+            error(PaymentChannelError::InstructionTooShort)
+        );
+
+        // Proof declared with u32::MAX hashes
+        let mut data = ChannelInstruction::LeaveWithProof {
+            channel_id: "channel".to_string(),
+            balance: 10,
+            leaf_index: 3,
+            proof: vec![],
+        }
+        .pack()
+        .unwrap();
+        let proof_len_at = data.len() - 4;
+        data[proof_len_at..].copy_from_slice(&u32::MAX.to_le_bytes());
+        data.extend([0; 32]);
+
+        assert_eq!(
+            ChannelInstruction::unpack(&data),
+            error(PaymentChannelError::InstructionTooShort)
+        );
+    }
+
+    #[test]
+    fn invalid_bool_or_utf8_is_invalid_payload() {
+        let mut data = every_instruction()[3].pack().unwrap(); // ModifyOracle
+        data[1 + 32] = 2; // oracle_status
+
+        assert_eq!(
+            ChannelInstruction::unpack(&data),
+            error(PaymentChannelError::InvalidInstructionPayload)
+        );
+
+        let mut data = vec![8];
+        data.extend(2u32.to_le_bytes());
+        data.extend([0xff, 0xfe]);
+
+        assert_eq!(
+            ChannelInstruction::unpack(&data),
+            error(PaymentChannelError::InvalidInstructionPayload)
+        );
     }
 }
@@ -1,7 +1,6 @@
 use borsh::BorshSerialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    borsh::try_from_slice_unchecked,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
@@ -12,7 +11,9 @@ use solana_program::{
 
 use crate::channel_state::ChannelState;
 use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
 use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
 
 pub fn invite(
     program_id: &Pubkey,
@@ -30,32 +31,29 @@ pub fn invite(
     let pda_invitee_account = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
 
-    let (pda_channel, _) =
-        Pubkey::find_program_address(&[channel_id.as_bytes().as_ref()], program_id);
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
 
     if pda_channel != *pda_channel_account.key {
         msg!("pda_channel != pda_channel_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     let (pda_stakeholder, _) = Pubkey::find_program_address(
-        &[channel_id.as_bytes().as_ref(), msg_sender.key.as_ref()],
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
         program_id,
     );
 
     if pda_stakeholder != *pda_stakeholder_account.key {
         msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
-    let (pda_invitee, pda_invitee_bump_seed) = Pubkey::find_program_address(
-        &[channel_id.as_bytes().as_ref(), invitee.as_ref()],
-        program_id,
-    );
+    let (pda_invitee, pda_invitee_bump_seed) =
+        Pubkey::find_program_address(&[channel_id.as_bytes(), invitee.as_ref()], program_id);
 
     if pda_invitee != *pda_invitee_account.key {
         msg!("pda_invitee != pda_invitee_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     // Load Stakeholder PDA
@@ -65,7 +63,7 @@ pub fn invite(
         Ok(data) => data,
         Err(_) => {
             msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::AccountNotInitialized.into());
         }
     };
 
@@ -73,7 +71,7 @@ pub fn invite(
     if pda_stakeholder_account_data.status != 2 {
         // sender must have status active
         msg!("msg.sender is not part of the channel");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
     }
 
     let pda_channel_account_data =
@@ -81,24 +79,24 @@ pub fn invite(
             Ok(data) => data,
             Err(_) => {
                 msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
-                return Err(PaymentChannelError::Error.into());
+                return Err(PaymentChannelError::AccountNotInitialized.into());
             }
         };
 
     // check that channel name = as in loader, and check that sender is invited
     if pda_channel_account_data.channel_id != channel_id {
         msg!("Wrong Channel ID");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
     }
 
     // Channel must be opened
     if pda_channel_account_data.current_status != 1 {
         msg!("Channel status != opened");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::ChannelNotOpened.into());
     }
 
     // Create pda_invitee
-    let pda_invitee_account_len: usize = 32 + 1 + 8; // // Pubkey (32 bytes) + u8 (1 byte) + u64 (8 bytes)
+    let pda_invitee_account_len: usize = 32 + 1 + 8 + 8 + 8 + 32; // // Pubkey (32 bytes) + u8 (1 byte) + u64 (8 bytes) + 2x u64 (16 bytes) + Pubkey (32 bytes)
 
     // Calculate rent required
     let pda_invitee_rent = Rent::get()?;
@@ -119,7 +117,7 @@ pub fn invite(
             system_program.clone(),
         ],
         &[&[
-            channel_id.as_bytes().as_ref(),
+            channel_id.as_bytes(),
             invitee.as_ref(),
             &[pda_invitee_bump_seed],
         ]],
@@ -127,7 +125,7 @@ pub fn invite(
         Ok(()) => (),
         Err(_) => {
             msg!("Stakeholder is already part of channel");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::AccountAlreadyInitialized.into());
         }
     }
 
@@ -138,11 +136,19 @@ pub fn invite(
     pda_invitee_account_data.stakeholder_address = invitee;
     pda_invitee_account_data.balance = 0;
     pda_invitee_account_data.status = 1; // ACTIVE
+    pda_invitee_account_data.rent_payer = *msg_sender.key; // inviter, refunded once the PDA is closed
 
     // Serialize PDA Stakeholder account - data
     pda_invitee_account_data.serialize(&mut &mut pda_invitee_account.data.borrow_mut()[..])?;
 
     msg!("Stakeholder succesfuly invited to channel!");
 
+    ChannelEvent::StakeholderInvited {
+        channel_id,
+        inviter: *msg_sender.key,
+        invitee,
+    }
+    .emit();
+
     Ok(())
 }
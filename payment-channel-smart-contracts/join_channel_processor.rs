@@ -1,41 +1,27 @@
 use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    borsh::try_from_slice_unchecked,
+    account_info::{next_account_info, next_account_infos, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program::invoke,
     pubkey::Pubkey,
     system_instruction,
+    sysvar::{clock::Clock, Sysvar},
 };
 
+use payment_channel_token::{JoinTokenData, TokenPayload, ACTION_JOIN};
+
 use crate::channel_state::ChannelState;
 use crate::error::PaymentChannelError;
-use crate::oracle_state::OracleState;
+use crate::events::ChannelEvent;
 use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{
+    check_token_account, check_token_program, check_vault, deposit_to_vault, is_token_channel,
+};
+use crate::try_from_slice_unchecked;
 use crate::verify_signature_processor::verify_ed25519;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct JoinTokenPayload {
-    encoded_data: Vec<u8>,
-    prev_state: String,
-    sig_oracle: [u8; 64],
-}
-
-use anchor_lang::prelude::*;
-
-#[derive(BorshDeserialize)]
-struct JoinTokenDataSchema {
-    // ENCODED DATA
-    action: u8,
-    channelid: String,
-    address: Pubkey,
-    balance: u64,
-    sender: Pubkey,
-    sig_sender: [u8; 64], // to be included later (maybe)
-}
-
 pub fn join_channel(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -49,9 +35,10 @@ pub fn join_channel(
     let msg_sender = next_account_info(account_info_iter)?;
     let pda_channel_account = next_account_info(account_info_iter)?;
     let pda_stakeholder_account = next_account_info(account_info_iter)?;
-    let pda_oracle_account = next_account_info(account_info_iter)?;
     let sysvar_account = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+    // Only for SPL Token denominated channels: vault_pda, sender token account, token program
 
     /*
         0. Load join_token
@@ -63,34 +50,39 @@ pub fn join_channel(
         6. Emit event
     */
 
-    let join_token_decoded = match JoinTokenPayload::try_from_slice(join_token_encoded.as_slice()) {
+    let join_token_decoded = match TokenPayload::try_from_slice(join_token_encoded.as_slice()) {
         Ok(payload) => payload,
         Err(_) => {
             msg!("Failed to decode join_token");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
         }
     };
 
     let join_token_core_data_decoded_result =
-        JoinTokenDataSchema::try_from_slice(join_token_decoded.encoded_data.as_slice());
+        JoinTokenData::try_from_slice(join_token_decoded.encoded_data.as_slice());
 
     let join_token_core_data_decoded = match join_token_core_data_decoded_result {
         Ok(data) => data,
         Err(_) => {
             msg!("Failed to decode join_token_core_data");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
         }
     };
 
+    if join_token_core_data_decoded.action != ACTION_JOIN {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
     // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
     let (pda_channel, _) = Pubkey::find_program_address(
-        &[join_token_core_data_decoded.channelid.as_bytes().as_ref()],
+        &[join_token_core_data_decoded.channelid.as_bytes()],
         program_id,
     );
 
     if pda_channel != *pda_channel_account.key {
         msg!("pda_channel != pda_channel_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     let mut pda_channel_account_data =
@@ -98,14 +90,14 @@ pub fn join_channel(
             Ok(data) => data,
             Err(_) => {
                 msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
-                return Err(PaymentChannelError::Error.into());
+                return Err(PaymentChannelError::AccountNotInitialized.into());
             }
         };
 
     // Load and check PDA_STAKEHOLDER / STAKEHOLDER ACCOUNT
     let (pda_stakeholder, _) = Pubkey::find_program_address(
         &[
-            join_token_core_data_decoded.channelid.as_bytes().as_ref(),
+            join_token_core_data_decoded.channelid.as_bytes(),
             msg_sender.key.as_ref(),
         ],
         program_id,
@@ -113,7 +105,7 @@ pub fn join_channel(
 
     if pda_stakeholder != *pda_stakeholder_account.key {
         msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     let mut pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
@@ -122,67 +114,91 @@ pub fn join_channel(
         Ok(data) => data,
         Err(_) => {
             msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::AccountNotInitialized.into());
         }
     };
 
-    let oracle_account_data =
-        match try_from_slice_unchecked::<OracleState>(&pda_oracle_account.data.borrow()) {
-            Ok(data) => data,
-            Err(_) => {
-                msg!("Oracle deserialization error");
-                return Err(PaymentChannelError::Error.into());
-            }
-        };
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, join_token_decoded.sig_oracles.len())?;
 
-    // Checks (Channel status = opened, Stakeholder status = invited, channelid matches, action in token = 2, balance > 0, stakeholder sender)
+    // Checks (Channel status = opened, Stakeholder status = invited, channelid matches, balance > 0, stakeholder sender)
     if pda_channel_account_data.channel_id != join_token_core_data_decoded.channelid {
         msg!("join_token.channel_id != channel.channel_id");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
     }
 
+    pda_channel_account_data.check_channel_nonce(join_token_core_data_decoded.channel_nonce)?;
+
     if pda_channel_account_data.current_status != 1 {
         msg!("Channel is not opened");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::ChannelNotOpened.into());
     }
 
     // if pda_stakeholder_account_data.stakeholder_address !=
     if pda_stakeholder_account_data.status != 1 {
         msg!("Stakeholder status != invited");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
     }
 
     if join_token_decoded.prev_state != "0" {
         msg!("Wrong 'prev_state' defined within provided token.");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::WrongPrevState.into());
     }
 
     // Sender should be the same as defined within token
     if *msg_sender.key != join_token_core_data_decoded.sender {
         msg!("Sender of this TX is not the same as defined in token");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::SenderMismatch.into());
     }
 
-    if join_token_core_data_decoded.balance <= 0 {
+    if join_token_core_data_decoded.balance == 0 {
         msg!("Provided amount is too low");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidAmount.into());
+    }
+
+    if join_token_core_data_decoded.sequence <= pda_channel_account_data.sequence {
+        msg!(
+            "Token sequence {} is not greater than channel sequence {}",
+            join_token_core_data_decoded.sequence,
+            pda_channel_account_data.sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
     }
 
+    // Verification of 'sender' signature - stakeholder must have signed the token data too
+    msg!("Verification of token signature (sig_sender)");
     match verify_ed25519(
         sysvar_account,
-        oracle_account_data.oracle_address, // pub key
-        join_token_decoded.encoded_data,    // msg
-        join_token_decoded.sig_oracle,      // sig
+        join_token_core_data_decoded.sender,           // pub key
+        join_token_decoded.sender_preimage().to_vec(), // msg
+        join_token_core_data_decoded.sig_sender,       // sig
     ) {
-        Ok(_) => msg!("Oracle signature succesfuly verified!"),
+        Ok(_) => msg!("Sender signature succesfuly verified!"),
         Err(_) => {
-            msg!("Oracle signature FAILED!");
-            return Err(PaymentChannelError::Error.into());
+            msg!("Verification of join_token (sig_sender) FAILED");
+            return Err(PaymentChannelError::InvalidSenderSignature.into());
         }
     }
 
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        join_token_decoded.oracle_preimage(),
+        &join_token_decoded.sig_oracles,
+        false,
+    )?;
+
     // assign values
     pda_channel_account_data.num_of_active_stakeholders += 1;
+    pda_channel_account_data.sequence = join_token_core_data_decoded.sequence;
+    pda_channel_account_data.last_checkpoint_slot = Clock::get()?.slot;
+    pda_channel_account_data.total_deposits = pda_channel_account_data
+        .total_deposits
+        .checked_add(join_token_core_data_decoded.balance)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
 
     // serialize
     pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
@@ -190,11 +206,47 @@ pub fn join_channel(
     // assign values
     pda_stakeholder_account_data.balance = join_token_core_data_decoded.balance;
     pda_stakeholder_account_data.status = 2;
+    pda_stakeholder_account_data.sequence = join_token_core_data_decoded.sequence;
 
     // serialize
     pda_stakeholder_account_data
         .serialize(&mut &mut pda_stakeholder_account.data.borrow_mut()[..])?;
 
+    ChannelEvent::StakeholderJoined {
+        channel_id: pda_channel_account_data.channel_id.clone(),
+        stakeholder: *msg_sender.key,
+        balance: join_token_core_data_decoded.balance,
+        sequence: join_token_core_data_decoded.sequence,
+        join_token: join_token_encoded.clone(),
+    }
+    .emit();
+
+    if is_token_channel(&pda_channel_account_data.mint) {
+        let vault_account = next_account_info(account_info_iter)?;
+        let sender_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        check_token_program(token_program)?;
+        check_vault(program_id, pda_channel_account.key, vault_account)?;
+        check_token_account(
+            sender_token_account,
+            &pda_channel_account_data.mint,
+            msg_sender.key,
+        )?;
+
+        msg!("Transfering Join amount (tokens) to channel vault");
+        deposit_to_vault(
+            sender_token_account,
+            vault_account,
+            msg_sender,
+            token_program,
+            join_token_core_data_decoded.balance,
+        )?;
+        msg!("Transfer completed, Stakeholder Joined!");
+
+        return Ok(());
+    }
+
     // // Send join amount to new channel
     let join_amount_transfer = system_instruction::transfer(
         msg_sender.key,          // From account
@@ -214,7 +266,5 @@ pub fn join_channel(
     )?;
     msg!("Transfer completed, Stakeholder Joined!");
 
-    msg!("JoinEvent: {:?}", join_token_encoded); // "event"
-
     Ok(())
 }
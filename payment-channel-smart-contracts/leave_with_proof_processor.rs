@@ -0,0 +1,174 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::leave_channel_processor::LEAVE_DISPUTE_WINDOW_SLOTS;
+use crate::merkle::{leaf_hash, verify_proof};
+use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
+
+// Leave based on the checkpointed balances root (see checkpoint_root_processor) instead of a
+// leave token; like leave_channel, funds are released by finalize_leave after the dispute window.
+// A claimed leaf is recorded on the stakeholder PDA itself (status 4, sequence = root sequence)
+// instead of a nullifier account, so nothing is left behind once the leave is finalized: the
+// leaf can not be claimed again while the PDA exists, and a stakeholder joining again gets a
+// sequence newer than the root.
+pub fn leave_with_proof(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    channel_id: String,
+    balance: u64,
+    leaf_index: u64,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?;
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_stakeholder_account = next_account_info(account_info_iter)?;
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("Wrong Channel ID");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    // Load and check PDA_STAKEHOLDER / STAKEHOLDER ACCOUNT
+    let (pda_stakeholder, _) = Pubkey::find_program_address(
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
+        program_id,
+    );
+
+    if pda_stakeholder != *pda_stakeholder_account.key {
+        msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_stakeholder_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    let root_sequence = pda_channel_account_data.balances_root_sequence;
+
+    if pda_stakeholder_account_data.status == 4
+        && pda_stakeholder_account_data.sequence == root_sequence
+    {
+        msg!("Balance proof has already been claimed");
+        return Err(PaymentChannelError::ProofAlreadyClaimed.into());
+    }
+
+    if pda_stakeholder_account_data.status != 2 {
+        msg!("Stakeholder status != active");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    if root_sequence == 0 {
+        msg!("No balances root checkpointed");
+        return Err(PaymentChannelError::CheckpointMismatch.into());
+    }
+
+    // Root is superseded once a later state has been checkpointed
+    if root_sequence < pda_channel_account_data.checkpoint_sequence {
+        msg!(
+            "Root sequence {} is older than checkpoint sequence {}",
+            root_sequence,
+            pda_channel_account_data.checkpoint_sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    // Root is stale for stakeholders whose balance changed on-chain afterwards (e.g. withdraw)
+    if pda_stakeholder_account_data.sequence >= root_sequence {
+        msg!(
+            "Stakeholder sequence {} is not older than root sequence {}",
+            pda_stakeholder_account_data.sequence,
+            root_sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    if balance == 0 {
+        msg!("Provided amount is too low");
+        return Err(PaymentChannelError::InvalidAmount.into());
+    }
+
+    let leaf = leaf_hash(msg_sender.key, balance, root_sequence);
+
+    if !verify_proof(
+        &pda_channel_account_data.balances_root,
+        &leaf,
+        leaf_index,
+        &proof,
+    ) {
+        msg!("Verification of balance proof FAILED");
+        return Err(PaymentChannelError::InvalidMerkleProof.into());
+    }
+
+    // Funds are not released yet - stakeholder enters the dispute window (see challenge_leave / finalize_leave)
+    let clock = Clock::get()?;
+
+    // assign values
+    pda_stakeholder_account_data.balance = balance;
+    pda_stakeholder_account_data.status = 4; // 4 = closing
+    pda_stakeholder_account_data.closing_slot = clock.slot + LEAVE_DISPUTE_WINDOW_SLOTS;
+    pda_stakeholder_account_data.sequence = root_sequence;
+
+    // Serialize PDA Stakeholder account - data
+    pda_stakeholder_account_data
+        .serialize(&mut &mut pda_stakeholder_account.data.borrow_mut()[..])?;
+
+    if root_sequence > pda_channel_account_data.sequence {
+        pda_channel_account_data.sequence = root_sequence;
+
+        pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+    }
+
+    msg!(
+        "Leave (proof) pending, amount {} can be claimed from slot {}",
+        balance,
+        pda_stakeholder_account_data.closing_slot
+    );
+
+    Ok(())
+}
@@ -1,23 +1,54 @@
-use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
-};
+use borsh::{maybestd::io::Result as IoResult, BorshDeserialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
+pub mod bond_state;
+pub mod challenge_leave_processor;
 pub mod channel_state;
+pub mod checkpoint_processor;
+pub mod checkpoint_root_processor;
+pub mod close_account;
+pub mod config_state;
+pub mod deposit_processor;
+pub mod emergency_exit_processor;
 pub mod error;
+pub mod events;
+pub mod finalize_leave_processor;
+pub mod initialize_config_processor;
 pub mod instruction;
 pub mod invite_channel_processor;
 pub mod join_channel_processor;
 pub mod leave_channel_processor;
+pub mod leave_with_proof_processor;
+pub mod merkle;
+pub mod migrate_processor;
 pub mod modify_oracle_processor;
+pub mod nonce_state;
 pub mod open_channel_processor;
 pub mod oracle_state;
+pub mod prove_oracle_fraud_processor;
+pub mod revoke_invite_processor;
+pub mod rotate_oracle_key_processor;
+pub mod settle_channel_processor;
 pub mod stakeholder_state;
+pub mod token_vault;
+pub mod transfer_admin_processor;
 pub mod verify_signature_processor;
+pub mod withdraw_processor;
 
 use instruction::ChannelInstruction; // channel instruction
 
+// Program ID returned at deployment (placeholder); payment-channel-sdk derives accounts with it
+solana_program::declare_id!("PaymentChanne111111111111111111111111111111");
+
+// Decode account data allocated with room to grow (trailing bytes are ignored)
+pub fn try_from_slice_unchecked<T: BorshDeserialize>(data: &[u8]) -> IoResult<T> {
+    T::deserialize(&mut &data[..])
+}
+
 // Entry point is a function call process_instruction
-entrypoint!(process_instruction);
+// (left out with the `no-entrypoint` feature, e.g. when used as a dependency by payment-channel-sdk)
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // tukaj se glede na to kaj se vrne kliče funkcija!!!!!
 
@@ -47,9 +78,23 @@ pub fn process_instruction(
             leave_channel_processor::leave_channel(program_id, accounts, leave_token_encoded)
         }
 
-        ChannelInstruction::ModifyOracle { oracle_address } => {
+        ChannelInstruction::ModifyOracle {
+            oracle_address,
+            oracle_status,
+            name,
+            endpoint_hash,
+            fee,
+        } => {
             // Make a call to `modify oracle` function
-            modify_oracle_processor::modify_oracle(program_id, accounts, oracle_address)
+            modify_oracle_processor::modify_oracle(
+                program_id,
+                accounts,
+                oracle_address,
+                oracle_status,
+                name,
+                endpoint_hash,
+                fee,
+            )
         }
 
         ChannelInstruction::InviteToChannel {
@@ -59,5 +104,136 @@ pub fn process_instruction(
             // Make a call to `modify oracle` function
             invite_channel_processor::invite(program_id, accounts, channel_id, invitee)
         }
+
+        ChannelInstruction::ChallengeLeave {
+            challenge_token_encoded,
+        } => {
+            // Make a call to `challenge leave` function
+            challenge_leave_processor::challenge_leave(
+                program_id,
+                accounts,
+                challenge_token_encoded,
+            )
+        }
+
+        ChannelInstruction::FinalizeLeave { channel_id } => {
+            // Make a call to `finalize leave` function
+            finalize_leave_processor::finalize_leave(program_id, accounts, channel_id)
+        }
+
+        ChannelInstruction::SettleChannel {
+            settle_token_encoded,
+        } => {
+            // Make a call to `settle channel` function
+            settle_channel_processor::settle_channel(program_id, accounts, settle_token_encoded)
+        }
+
+        ChannelInstruction::Deposit {
+            deposit_token_encoded,
+        } => {
+            // Make a call to `deposit` function
+            deposit_processor::deposit(program_id, accounts, deposit_token_encoded)
+        }
+
+        ChannelInstruction::Withdraw {
+            withdraw_token_encoded,
+        } => {
+            // Make a call to `withdraw` function
+            withdraw_processor::withdraw(program_id, accounts, withdraw_token_encoded)
+        }
+
+        ChannelInstruction::InitializeConfig { admin } => {
+            // Make a call to `initialize config` function
+            initialize_config_processor::initialize_config(program_id, accounts, admin)
+        }
+
+        ChannelInstruction::TransferAdmin { new_admin } => {
+            // Make a call to `transfer admin` function
+            transfer_admin_processor::transfer_admin(program_id, accounts, new_admin)
+        }
+
+        ChannelInstruction::AcceptAdmin => {
+            // Make a call to `accept admin` function
+            transfer_admin_processor::accept_admin(program_id, accounts)
+        }
+
+        ChannelInstruction::RotateOracleKey {
+            new_key,
+            handover_slots,
+        } => {
+            // Make a call to `rotate oracle key` function
+            rotate_oracle_key_processor::rotate_oracle_key(
+                program_id,
+                accounts,
+                new_key,
+                handover_slots,
+            )
+        }
+
+        ChannelInstruction::ProveOracleFraud {
+            first_token_encoded,
+            second_token_encoded,
+        } => {
+            // Make a call to `prove oracle fraud` function
+            prove_oracle_fraud_processor::prove_oracle_fraud(
+                program_id,
+                accounts,
+                first_token_encoded,
+                second_token_encoded,
+            )
+        }
+
+        ChannelInstruction::EmergencyExit { channel_id } => {
+            // Make a call to `emergency exit` function
+            emergency_exit_processor::emergency_exit(program_id, accounts, channel_id)
+        }
+
+        ChannelInstruction::Checkpoint {
+            checkpoint_token_encoded,
+            cid_token_encoded,
+        } => {
+            // Make a call to `checkpoint` function
+            checkpoint_processor::checkpoint(
+                program_id,
+                accounts,
+                checkpoint_token_encoded,
+                cid_token_encoded,
+            )
+        }
+
+        ChannelInstruction::CheckpointRoot { root_token_encoded } => {
+            // Make a call to `checkpoint root` function
+            checkpoint_root_processor::checkpoint_root(program_id, accounts, root_token_encoded)
+        }
+
+        ChannelInstruction::LeaveWithProof {
+            channel_id,
+            balance,
+            leaf_index,
+            proof,
+        } => {
+            // Make a call to `leave with proof` function
+            leave_with_proof_processor::leave_with_proof(
+                program_id, accounts, channel_id, balance, leaf_index, proof,
+            )
+        }
+
+        ChannelInstruction::RevokeInvite {
+            channel_id,
+            invitee,
+        } => {
+            // Make a call to `revoke invite` function
+            revoke_invite_processor::revoke_invite(program_id, accounts, channel_id, invitee)
+        }
+
+        ChannelInstruction::DeclineInvite { channel_id } => {
+            // Make a call to `decline invite` function
+            revoke_invite_processor::decline_invite(program_id, accounts, channel_id)
+        }
+
+        ChannelInstruction::MigrateChannel { channel_id } => {
+            // Make a call to `migrate channel` function
+            migrate_processor::migrate_channel(program_id, accounts, channel_id)
+        }
     }
 }
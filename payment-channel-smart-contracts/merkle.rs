@@ -0,0 +1,133 @@
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+// Max depth of the balances tree (up to 2^MAX_MERKLE_DEPTH stakeholders)
+pub const MAX_MERKLE_DEPTH: usize = 32;
+
+// Domain separation of leaves and inner nodes (second preimage protection)
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// leaf = sha256(0x00 || address || balance (u64 LE) || sequence (u64 LE))
+pub fn leaf_hash(address: &Pubkey, balance: u64, sequence: u64) -> [u8; 32] {
+    hashv(&[
+        &[LEAF_PREFIX],
+        address.as_ref(),
+        &balance.to_le_bytes(),
+        &sequence.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+// node = sha256(0x01 || left || right)
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[NODE_PREFIX], left, right]).to_bytes()
+}
+
+// `proof` lists the sibling hashes from the leaf up to the root; bit i of `index` (position of
+// the leaf) tells whether the node at level i is the right (1) or left (0) child
+pub fn verify_proof(root: &[u8; 32], leaf: &[u8; 32], index: u64, proof: &[[u8; 32]]) -> bool {
+    if proof.len() > MAX_MERKLE_DEPTH || index >> proof.len() != 0 {
+        return false;
+    }
+
+    let computed_root = proof
+        .iter()
+        .enumerate()
+        .fold(*leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 1 {
+                node_hash(sibling, &node)
+            } else {
+                node_hash(&node, sibling)
+            }
+        });
+
+    computed_root == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Root of the tree over `leaves` (number of leaves = power of two)
+    fn root_of(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| node_hash(&pair[0], &pair[1]))
+                .collect();
+        }
+
+        level[0]
+    }
+
+    fn leaves() -> Vec<[u8; 32]> {
+        (0..4)
+            .map(|i| leaf_hash(&Pubkey::new_unique(), 100 * (i + 1), 7))
+            .collect()
+    }
+
+    #[test]
+    fn leaf_and_node_hashes_are_domain_separated() {
+        let address = Pubkey::new_unique();
+        let (balance, sequence) = (100u64.to_le_bytes(), 7u64.to_le_bytes());
+
+        // Same preimage hashed as leaf and as inner node
+        assert_eq!(
+            leaf_hash(&address, 100, 7),
+            hashv(&[&[LEAF_PREFIX], address.as_ref(), &balance, &sequence]).to_bytes()
+        );
+        assert_ne!(
+            leaf_hash(&address, 100, 7),
+            hashv(&[&[NODE_PREFIX], address.as_ref(), &balance, &sequence]).to_bytes()
+        );
+
+        let (left, right) = ([1u8; 32], [2u8; 32]);
+        assert_ne!(
+            node_hash(&left, &right),
+            hashv(&[&[LEAF_PREFIX], &left, &right]).to_bytes()
+        );
+        assert_ne!(node_hash(&left, &right), node_hash(&right, &left));
+    }
+
+    #[test]
+    fn proof_verifies_for_every_leaf() {
+        let leaves = leaves();
+        let root = root_of(&leaves);
+
+        let proofs = [
+            vec![leaves[1], node_hash(&leaves[2], &leaves[3])],
+            vec![leaves[0], node_hash(&leaves[2], &leaves[3])],
+            vec![leaves[3], node_hash(&leaves[0], &leaves[1])],
+            vec![leaves[2], node_hash(&leaves[0], &leaves[1])],
+        ];
+
+        for (index, proof) in proofs.iter().enumerate() {
+            assert!(verify_proof(&root, &leaves[index], index as u64, proof));
+        }
+    }
+
+    #[test]
+    fn proof_is_rejected_for_wrong_leaf_index_or_root() {
+        let leaves = leaves();
+        let root = root_of(&leaves);
+        let proof = vec![leaves[1], node_hash(&leaves[2], &leaves[3])];
+
+        assert!(!verify_proof(&root, &leaves[0], 1, &proof));
+        assert!(!verify_proof(&root, &leaves[1], 0, &proof));
+        // index out of range for the proof length
+        assert!(!verify_proof(&root, &leaves[0], 4, &proof));
+        assert!(!verify_proof(&[0; 32], &leaves[0], 0, &proof));
+    }
+
+    #[test]
+    fn proof_longer_than_max_depth_is_rejected() {
+        let leaf = leaf_hash(&Pubkey::new_unique(), 1, 1);
+        let proof = vec![[0u8; 32]; MAX_MERKLE_DEPTH + 1];
+        let root = proof
+            .iter()
+            .fold(leaf, |node, sibling| node_hash(&node, sibling));
+
+        assert!(!verify_proof(&root, &leaf, 0, &proof));
+    }
+}
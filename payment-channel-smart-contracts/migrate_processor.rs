@@ -0,0 +1,304 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+
+use crate::channel_state::{ChannelState, MAX_CHECKPOINT_CID_LEN};
+use crate::emergency_exit_processor::MIN_EMERGENCY_TIMEOUT_SLOTS;
+use crate::error::PaymentChannelError;
+use crate::oracle_state::{OracleState, MAX_ORACLE_NAME_LEN};
+use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
+
+// Layouts of accounts created by the first version of the program (single oracle, no sequence,
+// no rent payer). They are only read here, to move the accounts to the current layout.
+
+// oracle_pda, allocated with 36 bytes
+#[derive(BorshDeserialize)]
+pub struct LegacyOracleState {
+    pub oracle_address: Pubkey,
+    pub oracle_status: bool,
+}
+
+pub const LEGACY_ORACLE_STATE_LEN: usize = 2 + 32 + 2;
+
+// channel_pda, allocated with 4 + channel_id.len() + 32 + 1 + 1 bytes
+#[derive(BorshDeserialize)]
+pub struct LegacyChannelState {
+    pub channel_id: String,
+    pub oracle_address: Pubkey,
+    pub current_status: u8,
+    pub num_of_active_stakeholders: u8,
+}
+
+// stakeholder_pda, allocated with 32 + 1 + 8 bytes
+#[derive(BorshDeserialize)]
+pub struct LegacyStakeholderState {
+    pub stakeholder_address: Pubkey,
+    pub balance: u64,
+    pub status: u8,
+}
+
+pub const LEGACY_STAKEHOLDER_STATE_LEN: usize = 32 + 8 + 1;
+
+// Grow a program-owned account to `account_len`; `payer` tops up the rent
+fn realloc_account<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    account_len: usize,
+) -> ProgramResult {
+    let rent_lamports = Rent::get()?.minimum_balance(account_len);
+    let top_up = rent_lamports.saturating_sub(account.lamports());
+
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    account.realloc(account_len, true)
+}
+
+// Move an oracle registry entry of the legacy layout to the current one (called by modify_oracle,
+// the admin pays for the extra space). The oracle keeps signing with its identity key.
+pub fn migrate_oracle<'a>(
+    oracle_state_account: &AccountInfo<'a>,
+    admin: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<OracleState, ProgramError> {
+    let legacy_oracle_account_data =
+        match try_from_slice_unchecked::<LegacyOracleState>(&oracle_state_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize legacy ORACLE account data");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    msg!(
+        "Migrating ORACLE account {} to the current layout",
+        legacy_oracle_account_data.oracle_address
+    );
+
+    // Same as at registration (modify_oracle)
+    let account_len: usize = 32 + 1 + (4 + MAX_ORACLE_NAME_LEN) + 32 + 8 + 32 + 32 + 8 + 4;
+    realloc_account(oracle_state_account, admin, system_program, account_len)?;
+
+    Ok(OracleState {
+        oracle_address: legacy_oracle_account_data.oracle_address,
+        oracle_status: legacy_oracle_account_data.oracle_status,
+        name: String::new(),
+        endpoint_hash: [0; 32],
+        fee: 0,
+        current_key: legacy_oracle_account_data.oracle_address,
+        previous_key: Pubkey::default(),
+        previous_key_expiry_slot: 0,
+        key_version: 0,
+    })
+}
+
+// Move a channel of the legacy layout, together with its stakeholder PDAs, to the current layout.
+// The legacy layout does not record who paid the rent, so the channel rent goes to msg_sender
+// (who pays for the extra space) and stakeholder PDA rent to the stakeholders themselves.
+pub fn migrate_channel(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    channel_id: String,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // stakeholder of the channel, pays the extra rent
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    // Remaining accounts: every stakeholder PDA of the channel (active and invited)
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    if pda_channel_account.owner != program_id {
+        msg!("CHANNEL account is not owned by the program");
+        return Err(PaymentChannelError::InvalidOwner.into());
+    }
+
+    // Legacy channels are told apart by their size
+    if pda_channel_account.data_len() != 4 + channel_id.len() + 32 + 1 + 1 {
+        msg!("Channel is not of the legacy layout");
+        return Err(PaymentChannelError::AccountAlreadyInitialized.into());
+    }
+
+    let legacy_channel_account_data =
+        match try_from_slice_unchecked::<LegacyChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize legacy PDA_CHANNEL_ACCOUNT_DATA");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if legacy_channel_account_data.channel_id != channel_id {
+        msg!("Wrong Channel ID");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    let mut num_of_active_stakeholders: u8 = 0;
+    let mut total_deposits: u64 = 0;
+    let mut msg_sender_in_channel = false;
+
+    for pda_stakeholder_account in account_info_iter {
+        if pda_stakeholder_account.owner != program_id
+            || pda_stakeholder_account.data_len() != LEGACY_STAKEHOLDER_STATE_LEN
+        {
+            msg!(
+                "Stakeholder PDA {} is not of the legacy layout",
+                pda_stakeholder_account.key
+            );
+            return Err(PaymentChannelError::InvalidArgument.into());
+        }
+
+        let legacy_stakeholder_account_data = match try_from_slice_unchecked::<LegacyStakeholderState>(
+            &pda_stakeholder_account.data.borrow(),
+        ) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize legacy Stakeholder PDA data");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+        let (pda_stakeholder, _) = Pubkey::find_program_address(
+            &[
+                channel_id.as_bytes(),
+                legacy_stakeholder_account_data.stakeholder_address.as_ref(),
+            ],
+            program_id,
+        );
+
+        if pda_stakeholder != *pda_stakeholder_account.key {
+            msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+            return Err(PaymentChannelError::InvalidSeeds.into());
+        }
+
+        if legacy_stakeholder_account_data.status == 2 {
+            num_of_active_stakeholders += 1;
+            total_deposits = total_deposits
+                .checked_add(legacy_stakeholder_account_data.balance)
+                .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+            if legacy_stakeholder_account_data.stakeholder_address == *msg_sender.key {
+                msg_sender_in_channel = true;
+            }
+        }
+
+        // Same as in open_channel / join_channel
+        let pda_stakeholder_account_len: usize = 32 + 1 + 8 + 8 + 8 + 32;
+        realloc_account(
+            pda_stakeholder_account,
+            msg_sender,
+            system_program,
+            pda_stakeholder_account_len,
+        )?;
+
+        let pda_stakeholder_account_data = StakeholderState {
+            stakeholder_address: legacy_stakeholder_account_data.stakeholder_address,
+            balance: legacy_stakeholder_account_data.balance,
+            status: legacy_stakeholder_account_data.status,
+            closing_slot: 0,
+            sequence: 0,
+            rent_payer: legacy_stakeholder_account_data.stakeholder_address,
+        };
+
+        pda_stakeholder_account_data
+            .serialize(&mut &mut pda_stakeholder_account.data.borrow_mut()[..])?;
+    }
+
+    // Every active stakeholder has to be migrated along, so total_deposits is complete
+    if num_of_active_stakeholders != legacy_channel_account_data.num_of_active_stakeholders {
+        msg!(
+            "{} of {} active stakeholder PDAs provided",
+            num_of_active_stakeholders,
+            legacy_channel_account_data.num_of_active_stakeholders
+        );
+        return Err(PaymentChannelError::StateMismatch.into());
+    }
+
+    if !msg_sender_in_channel {
+        msg!("msg.sender is not an active stakeholder of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Same as in open_channel (single oracle)
+    let pda_channel_account_len: usize = 4
+        + channel_id.len()
+        + 4
+        + 32
+        + 1
+        + 1
+        + 1
+        + 8
+        + 32
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 4
+        + MAX_CHECKPOINT_CID_LEN
+        + 32
+        + 8
+        + 32
+        + 8;
+    realloc_account(
+        pda_channel_account,
+        msg_sender,
+        system_program,
+        pda_channel_account_len,
+    )?;
+
+    let pda_channel_account_data = ChannelState {
+        channel_id,
+        oracle_addresses: vec![legacy_channel_account_data.oracle_address],
+        oracle_threshold: 1,
+        current_status: legacy_channel_account_data.current_status,
+        num_of_active_stakeholders,
+        sequence: 0,
+        mint: Pubkey::default(),
+        total_deposits,
+        last_checkpoint_slot: Clock::get()?.slot,
+        emergency_timeout_slots: MIN_EMERGENCY_TIMEOUT_SLOTS,
+        checkpoint_hash: [0; 32],
+        checkpoint_sequence: 0,
+        checkpoint_cid: String::new(),
+        balances_root: [0; 32],
+        balances_root_sequence: 0,
+        opener: *msg_sender.key,
+        channel_nonce: 0, // no NONCE PDA yet, reopening the channel id takes nonce 1
+    };
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Channel {} migrated to the current layout",
+        pda_channel_account_data.channel_id
+    );
+
+    Ok(())
+}
@@ -1,7 +1,6 @@
 use borsh::BorshSerialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    borsh::try_from_slice_unchecked,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
@@ -10,14 +9,23 @@ use solana_program::{
     system_instruction,
     sysvar::{rent::Rent, Sysvar},
 };
-use std::str::FromStr;
 
-use crate::oracle_state::OracleState;
+use crate::bond_state::{BondState, BOND_SEED, ORACLE_BOND_LAMPORTS};
+use crate::config_state::check_admin;
+use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
+use crate::migrate_processor::{migrate_oracle, LEGACY_ORACLE_STATE_LEN};
+use crate::oracle_state::{OracleState, MAX_ORACLE_NAME_LEN};
+use crate::try_from_slice_unchecked;
 
 pub fn modify_oracle(
     program_id: &Pubkey,
     accounts: &[AccountInfo], // TO PRIDE NOTRI KO KLIČEMO?!!?!??
     oracle_address: Pubkey,
+    oracle_status: bool,
+    name: String,
+    endpoint_hash: [u8; 32],
+    fee: u64,
 ) -> ProgramResult {
     msg!("Entering modify oracle function");
 
@@ -25,27 +33,51 @@ pub fn modify_oracle(
 
     // Get accounts
     let initializer = next_account_info(account_info_iter)?; // msg.sender :)
-    let oracle_state_account = next_account_info(account_info_iter)?; // get - we will have only one oracle for PoC purposes
+    let oracle_state_account = next_account_info(account_info_iter)?; // registry entry of `oracle_address`
     let system_program = next_account_info(account_info_iter)?;
+    let pda_config_account = next_account_info(account_info_iter)?; // program config (admin)
+                                                                    // Only at registration / reactivation: oracle (signer, posts the bond), bond_pda
 
-    // Hardcoded "owner" public key
-    let initial_owner_string =
-        Pubkey::from_str("FXCB4QMbUFC4B8zpo7Hx4ihm3RL5mt7LQoVJbrdRiHhJ").unwrap();
-
-    // check that tx sender is owner of system program // ni tako trivialno!
+    // check that tx sender is the admin stored within program config
     msg!("Initializer KEY {}", initializer.key);
-    msg!("initial owner KEY {} ", initial_owner_string);
-    if initializer.key != &initial_owner_string {
-        return Err(ProgramError::IllegalOwner);
+    check_admin(program_id, initializer, pda_config_account)?;
+
+    let (pda_oracle, pda_oracle_bump_seed) =
+        Pubkey::find_program_address(&[oracle_address.as_ref()], program_id);
+
+    if pda_oracle != *oracle_state_account.key {
+        msg!("pda_oracle != oracle_state_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    if name.len() > MAX_ORACLE_NAME_LEN {
+        msg!("Oracle name too long (max {} bytes)", MAX_ORACLE_NAME_LEN);
+        return Err(PaymentChannelError::InvalidArgument.into());
     }
 
     // if no data - ORACLE account data does not exist
-    if AccountInfo::data_is_empty(oracle_state_account) == false {
-        // check owner also here
+    if !AccountInfo::data_is_empty(oracle_state_account) {
         msg!("ORACLE account DATA already exist");
+
+        if oracle_state_account.owner != program_id {
+            msg!("ORACLE account is not owned by the program");
+            return Err(PaymentChannelError::InvalidOwner.into());
+        }
+
         msg!("Unpacking ORACLE account data");
-        let mut oracle_account_data =
-            try_from_slice_unchecked::<OracleState>(&oracle_state_account.data.borrow()).unwrap();
+        let mut oracle_account_data = if oracle_state_account.data_len() == LEGACY_ORACLE_STATE_LEN
+        {
+            // Registered by the first version of the program
+            migrate_oracle(oracle_state_account, initializer, system_program)?
+        } else {
+            match try_from_slice_unchecked::<OracleState>(&oracle_state_account.data.borrow()) {
+                Ok(data) => data,
+                Err(_) => {
+                    msg!("Failed to deserialize ORACLE account data");
+                    return Err(PaymentChannelError::AccountNotInitialized.into());
+                }
+            }
+        };
         msg!("Borrowed ORACLE Account data");
 
         msg!(
@@ -53,28 +85,40 @@ pub fn modify_oracle(
             oracle_account_data.oracle_status
         );
 
-        if oracle_account_data.oracle_status == true {
-            let new_status = false;
-            msg!("Changing it to: {}", new_status);
-            oracle_account_data.oracle_status = new_status;
-        } else {
-            let new_status = true;
-            msg!("Changing it to: {}", new_status);
-            oracle_account_data.oracle_status = new_status;
+        // Suspended (e.g. slashed) oracle is only reactivated with its full bond at stake; oracles
+        // registered by the first version of the program post theirs now
+        if oracle_status && !oracle_account_data.oracle_status {
+            let oracle_account = next_account_info(account_info_iter)?;
+            let pda_bond_account = next_account_info(account_info_iter)?;
+
+            if pda_bond_account.data_is_empty() {
+                post_bond(
+                    program_id,
+                    oracle_account,
+                    pda_bond_account,
+                    &oracle_address,
+                    system_program,
+                )?;
+            } else {
+                check_bond(program_id, pda_bond_account, &oracle_address)?;
+            }
         }
 
+        msg!("Changing it to: {}", oracle_status);
+        oracle_account_data.oracle_status = oracle_status;
+        oracle_account_data.name = name;
+        oracle_account_data.endpoint_hash = endpoint_hash;
+        oracle_account_data.fee = fee;
+
         msg!("Serializing ORACLE data account");
         oracle_account_data.serialize(&mut &mut oracle_state_account.data.borrow_mut()[..])?;
         msg!("Oracle data account serialized");
     } else {
         msg!("ORACLE data account does not exist yet");
-        let (pda_oracle, pda_oracle_bump_seed) =
-            Pubkey::find_program_address(&[oracle_address.as_ref()], program_id);
-
         msg!("Oracle PDA generated {}", pda_oracle);
 
         // Calculate account size required
-        let account_len: usize = 2 + 32 + 2; // boolean (2bytes) + Pubkey (32 bytes) + boolean (2 bytes)
+        let account_len: usize = 32 + 1 + (4 + MAX_ORACLE_NAME_LEN) + 32 + 8 + 32 + 32 + 8 + 4; // Pubkey + bool + String (max len) + [u8; 32] + u64 + keys (2x Pubkey + u64 + u32)
 
         // Calculate rent required
         let rent = Rent::get()?;
@@ -107,10 +151,129 @@ pub fn modify_oracle(
 
         // oracle_account_data.is_initialized = true;
         oracle_account_data.oracle_address = oracle_address;
-        oracle_account_data.oracle_status = true;
+        oracle_account_data.oracle_status = oracle_status;
+        oracle_account_data.name = name;
+        oracle_account_data.endpoint_hash = endpoint_hash;
+        oracle_account_data.fee = fee;
+        oracle_account_data.current_key = oracle_address; // initial signing key = oracle identity
+        oracle_account_data.key_version = 0;
         msg!("Serializing ORACLE account data");
         oracle_account_data.serialize(&mut &mut oracle_state_account.data.borrow_mut()[..])?;
         msg!("ORACLE account data serialized");
+
+        let oracle_account = next_account_info(account_info_iter)?;
+        let pda_bond_account = next_account_info(account_info_iter)?;
+
+        post_bond(
+            program_id,
+            oracle_account,
+            pda_bond_account,
+            &oracle_address,
+            system_program,
+        )?;
+    }
+
+    ChannelEvent::OracleModified {
+        oracle_address,
+        oracle_status,
+        fee,
+    }
+    .emit();
+
+    Ok(())
+}
+
+// Oracle puts ORACLE_BOND_LAMPORTS at stake into its bond PDA
+fn post_bond<'a>(
+    program_id: &Pubkey,
+    oracle_account: &AccountInfo<'a>,
+    pda_bond_account: &AccountInfo<'a>,
+    oracle_address: &Pubkey,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if *oracle_account.key != *oracle_address || !oracle_account.is_signer {
+        msg!("Oracle {} must sign to post its bond", oracle_address);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (pda_bond, pda_bond_bump_seed) =
+        Pubkey::find_program_address(&[BOND_SEED, oracle_address.as_ref()], program_id);
+
+    if pda_bond != *pda_bond_account.key {
+        msg!("pda_bond != pda_bond_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_bond_account_len: usize = 32 + 8; // Pubkey + u64
+    let pda_bond_lamports = Rent::get()?
+        .minimum_balance(pda_bond_account_len)
+        .checked_add(ORACLE_BOND_LAMPORTS)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            oracle_account.key,
+            pda_bond_account.key,
+            pda_bond_lamports,
+            pda_bond_account_len.try_into().unwrap(),
+            program_id,
+        ),
+        &[
+            oracle_account.clone(),
+            pda_bond_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[BOND_SEED, oracle_address.as_ref(), &[pda_bond_bump_seed]]], // bond pda seed = "bond"+oracle_address
+    )?;
+
+    let mut pda_bond_account_data =
+        try_from_slice_unchecked::<BondState>(&pda_bond_account.data.borrow()).unwrap();
+
+    pda_bond_account_data.oracle_address = *oracle_address;
+    pda_bond_account_data.amount = ORACLE_BOND_LAMPORTS;
+
+    pda_bond_account_data.serialize(&mut &mut pda_bond_account.data.borrow_mut()[..])?;
+
+    msg!("Oracle bond posted: {} lamports", ORACLE_BOND_LAMPORTS);
+
+    Ok(())
+}
+
+// Bond of an oracle being reactivated must not have been slashed
+fn check_bond(
+    program_id: &Pubkey,
+    pda_bond_account: &AccountInfo,
+    oracle_address: &Pubkey,
+) -> ProgramResult {
+    let (pda_bond, _) =
+        Pubkey::find_program_address(&[BOND_SEED, oracle_address.as_ref()], program_id);
+
+    if pda_bond != *pda_bond_account.key {
+        msg!("pda_bond != pda_bond_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    if pda_bond_account.owner != program_id {
+        msg!("BOND account is not owned by the program");
+        return Err(PaymentChannelError::InvalidOwner.into());
+    }
+
+    let pda_bond_account_data =
+        match try_from_slice_unchecked::<BondState>(&pda_bond_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize BOND account");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_bond_account_data.amount < ORACLE_BOND_LAMPORTS {
+        msg!(
+            "Oracle bond is {} lamports, {} required",
+            pda_bond_account_data.amount,
+            ORACLE_BOND_LAMPORTS
+        );
+        return Err(PaymentChannelError::BondAlreadySlashed.into());
     }
 
     Ok(())
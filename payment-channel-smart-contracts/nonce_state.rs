@@ -0,0 +1,12 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+// nonce_pda = ("nonce"+channelid); created by the first open of a channel id and never closed, so
+// it outlives the channel PDA. Every open takes the next nonce and all tokens of the channel carry
+// it (see ChannelState::check_channel_nonce). Channel ids starting with "nonce" are rejected, see
+// channel_state::check_channel_id
+pub const NONCE_SEED: &[u8] = b"nonce";
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct NonceState {
+    pub channel_nonce: u64, // nonce of the latest opened channel, 0 = never opened
+}
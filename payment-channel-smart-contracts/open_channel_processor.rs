@@ -1,41 +1,31 @@
 use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    borsh::try_from_slice_unchecked,
+    account_info::{next_account_info, next_account_infos, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program::invoke,
     program::invoke_signed,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
-use crate::channel_state::ChannelState;
+use payment_channel_token::{OpenTokenData, TokenPayload, ACTION_OPEN};
+
+use crate::channel_state::{check_channel_id, ChannelState, MAX_CHECKPOINT_CID_LEN};
+use crate::emergency_exit_processor::MIN_EMERGENCY_TIMEOUT_SLOTS;
 use crate::error::PaymentChannelError;
-use crate::oracle_state::OracleState;
+use crate::events::ChannelEvent;
+use crate::nonce_state::{NonceState, NONCE_SEED};
+use crate::oracle_state::OracleQuorum;
 use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{
+    check_token_account, check_token_program, create_vault, deposit_to_vault, is_token_channel,
+};
+use crate::try_from_slice_unchecked;
 use crate::verify_signature_processor::verify_ed25519;
 
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct OpenTokenPayload {
-    encoded_data: Vec<u8>,
-    prev_state: String,
-    sig_oracle: [u8; 64],
-}
-
-#[derive(BorshDeserialize)]
-struct OpenTokenDataSchema {
-    // ENCODED DATA
-    action: u8,
-    channelid: String,
-    address: Pubkey,
-    balance: u64,
-    sender: Pubkey,
-    sig_sender: [u8; 64], // to be checked maybe later
-}
-
 pub fn open_channel(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -48,80 +38,180 @@ pub fn open_channel(
     let msg_sender = next_account_info(account_info_iter)?;
     let pda_channel_account = next_account_info(account_info_iter)?;
     let pda_stakeholder_account = next_account_info(account_info_iter)?;
-    let pda_oracle_account = next_account_info(account_info_iter)?;
+    let pda_nonce_account = next_account_info(account_info_iter)?;
     let sysvar_account = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+    // Only for SPL Token denominated channels (mint defined within token):
+    // mint, vault_pda, sender token account, token program
 
-    let open_token_decoded = match OpenTokenPayload::try_from_slice(open_token_encoded.as_slice()) {
+    let open_token_decoded = match TokenPayload::try_from_slice(open_token_encoded.as_slice()) {
         Ok(payload) => payload,
         Err(_) => {
             msg!("Failed to decode open_token");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
         }
     };
 
     // Deserelize open_token
     let open_token_core_data_decoded_result =
-        OpenTokenDataSchema::try_from_slice(open_token_decoded.encoded_data.as_slice());
+        OpenTokenData::try_from_slice(open_token_decoded.encoded_data.as_slice());
 
     let open_token_core_data_decoded = match open_token_core_data_decoded_result {
         Ok(data) => data,
         Err(_) => {
             msg!("Failed to decode open_token_core_data");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
         }
     };
 
     // Check data
-    if open_token_core_data_decoded.action != 1 {
+    if open_token_core_data_decoded.action != ACTION_OPEN {
         msg!("Wrong 'action' defined within provided token.");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::WrongAction.into());
     }
 
     if open_token_decoded.prev_state != "0" {
         msg!("Wrong 'prev_state' defined within provided token.");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::WrongPrevState.into());
     }
 
+    check_channel_id(&open_token_core_data_decoded.channelid)?;
+
     // Sender should be the same as defined within token
     if *msg_sender.key != open_token_core_data_decoded.sender {
         msg!("Sender of this TX is not the same as defined in token");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::SenderMismatch.into());
     }
 
-    if open_token_core_data_decoded.balance <= 0 {
+    if open_token_core_data_decoded.balance == 0 {
         msg!("Provided amount is too low");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidAmount.into());
     }
 
-    // Unpack oracle account
-    let oracle_account_data =
-        match try_from_slice_unchecked::<OracleState>(&pda_oracle_account.data.borrow()) {
-            Ok(data) => data,
-            Err(_) => {
-                msg!("Failed to deserialize ORACLE Account - wrong Oracle account provided");
-                return Err(PaymentChannelError::Error.into());
-            }
-        };
+    // Channel does not exist yet, so stored sequence is 0
+    if open_token_core_data_decoded.sequence == 0 {
+        msg!("Token sequence must be greater than 0");
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    if open_token_core_data_decoded.emergency_timeout_slots < MIN_EMERGENCY_TIMEOUT_SLOTS {
+        msg!(
+            "Emergency timeout must be at least {} slots",
+            MIN_EMERGENCY_TIMEOUT_SLOTS
+        );
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
 
-    // Verification of 'oracle' signature (TO-DO - if possible, consider also swithicng back to old strategy that includes verification of both signatures!)
-    msg!("Verification of token signature (sig_oracle)");
+    // Opener chooses the oracles (registry entries) the channel is bound to
+    let oracle_quorum = OracleQuorum {
+        oracles: &open_token_core_data_decoded.oracles,
+        threshold: open_token_core_data_decoded.oracle_threshold,
+    };
+    oracle_quorum.check_config()?;
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, open_token_decoded.sig_oracles.len())?;
+
+    // Verification of 'sender' signature - stakeholder must have signed the token data too
+    msg!("Verification of token signature (sig_sender)");
     match verify_ed25519(
         sysvar_account,
-        oracle_account_data.oracle_address, // pub key
-        open_token_decoded.encoded_data,    // msg
-        open_token_decoded.sig_oracle,      // sig
+        open_token_core_data_decoded.sender,           // pub key
+        open_token_decoded.sender_preimage().to_vec(), // msg
+        open_token_core_data_decoded.sig_sender,       // sig
     ) {
-        Ok(_) => msg!("Oracle signature succesfuly verified!"),
+        Ok(_) => msg!("Sender signature succesfuly verified!"),
         Err(_) => {
-            msg!("Verification of open_token (sig_oracle) FAILED");
-            return Err(PaymentChannelError::Error.into());
+            msg!("Verification of open_token (sig_sender) FAILED");
+            return Err(PaymentChannelError::InvalidSenderSignature.into());
         }
     }
 
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    oracle_quorum.verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        open_token_decoded.oracle_preimage(),
+        &open_token_decoded.sig_oracles,
+        false,
+    )?;
+
+    // Channel id may have been used before - token must be issued for its next incarnation
+    let (pda_nonce, pda_nonce_bump_seed) = Pubkey::find_program_address(
+        &[
+            NONCE_SEED,
+            open_token_core_data_decoded.channelid.as_bytes(),
+        ],
+        program_id,
+    );
+
+    if pda_nonce != *pda_nonce_account.key {
+        msg!("pda_nonce != pda_nonce_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let stored_channel_nonce = if pda_nonce_account.data_is_empty() {
+        // First open of the channel id - create NONCE PDA (u64, never closed)
+        let pda_nonce_account_len: usize = 8;
+        let pda_nonce_rent_lamports = Rent::get()?.minimum_balance(pda_nonce_account_len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                msg_sender.key,
+                pda_nonce_account.key,
+                pda_nonce_rent_lamports,
+                pda_nonce_account_len as u64,
+                program_id,
+            ),
+            &[
+                msg_sender.clone(),
+                pda_nonce_account.clone(),
+                system_program.clone(),
+            ],
+            &[&[
+                NONCE_SEED,
+                open_token_core_data_decoded.channelid.as_bytes(),
+                &[pda_nonce_bump_seed],
+            ]], // nonce pda seed = "nonce"+channelid
+        )?;
+
+        0
+    } else {
+        if pda_nonce_account.owner != program_id {
+            msg!("NONCE account is not owned by the program");
+            return Err(PaymentChannelError::InvalidOwner.into());
+        }
+
+        match try_from_slice_unchecked::<NonceState>(&pda_nonce_account.data.borrow()) {
+            Ok(data) => data.channel_nonce,
+            Err(_) => {
+                msg!("Failed to deserialize NONCE account");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        }
+    };
+
+    let channel_nonce = stored_channel_nonce
+        .checked_add(1)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    if open_token_core_data_decoded.channel_nonce != channel_nonce {
+        msg!(
+            "Token channel nonce {} != next channel nonce {}",
+            open_token_core_data_decoded.channel_nonce,
+            channel_nonce
+        );
+        return Err(PaymentChannelError::ChannelNonceMismatch.into());
+    }
+
+    NonceState { channel_nonce }.serialize(&mut &mut pda_nonce_account.data.borrow_mut()[..])?;
+
     let (pda_stakeholder, pda_stakeholder_bump_seed) = Pubkey::find_program_address(
         &[
-            open_token_core_data_decoded.channelid.as_bytes().as_ref(),
+            open_token_core_data_decoded.channelid.as_bytes(),
             open_token_core_data_decoded.sender.as_ref(),
         ],
         program_id,
@@ -129,7 +219,7 @@ pub fn open_channel(
 
     if pda_stakeholder != *pda_stakeholder_account.key {
         msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     // // Calculate account size required
@@ -137,7 +227,10 @@ pub fn open_channel(
     // // - pub stakeholder_address: Pubkey,
     // // - pub status: u8, // { 1 = INVITED, 2 = ACTIVE, 3 = INACTIVE }
     // // - pub balance: u64,
-    let pda_stakeholder_account_len: usize = 32 + 1 + 8; // // Pubkey (32 bytes) + u8 (1 byte) + u64 (8 bytes)
+    // // - pub closing_slot: u64,
+    // // - pub sequence: u64,
+    // // - pub rent_payer: Pubkey,
+    let pda_stakeholder_account_len: usize = 32 + 1 + 8 + 8 + 8 + 32; // // Pubkey (32 bytes) + u8 (1 byte) + u64 (8 bytes) + 2x u64 (16 bytes) + Pubkey (32 bytes)
 
     // Calculate rent required
     let pda_stakeholder_rent = Rent::get()?;
@@ -159,7 +252,7 @@ pub fn open_channel(
             system_program.clone(),
         ],
         &[&[
-            open_token_core_data_decoded.channelid.as_bytes().as_ref(),
+            open_token_core_data_decoded.channelid.as_bytes(),
             open_token_core_data_decoded.sender.as_ref(),
             &[pda_stakeholder_bump_seed],
         ]], // seed = channelid+stakeholderPubKey (+ bump seed)
@@ -167,7 +260,7 @@ pub fn open_channel(
         Ok(()) => (),
         Err(_) => {
             msg!("Stakeholder is already part of channel");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::AccountAlreadyInitialized.into());
         }
     }
 
@@ -179,26 +272,46 @@ pub fn open_channel(
     stakeholder_account_data.stakeholder_address = open_token_core_data_decoded.address;
     stakeholder_account_data.balance = open_token_core_data_decoded.balance;
     stakeholder_account_data.status = 2; // 2 = active
+    stakeholder_account_data.sequence = open_token_core_data_decoded.sequence;
+    stakeholder_account_data.rent_payer = *msg_sender.key;
 
     // Serialize PDA Stakeholder account - data
     stakeholder_account_data.serialize(&mut &mut pda_stakeholder_account.data.borrow_mut()[..])?;
 
     // Creation of Channel PDA
     let (pda_channel, pda_channel_bump_seed) = Pubkey::find_program_address(
-        &[open_token_core_data_decoded.channelid.as_bytes().as_ref()],
+        &[open_token_core_data_decoded.channelid.as_bytes()],
         program_id,
     );
 
     if pda_channel != *pda_channel_account.key {
         msg!("pda_channel != pda_channel_account.key; invalid seeds");
-        return Err(PaymentChannelError::Error.into());
+        return Err(PaymentChannelError::InvalidSeeds.into());
     }
 
     // Calculate account size required
     // let pda_channel_account_len: usize =
     // 4 + open_token_core_data_decoded.channelid.len() + 32 + 320 + 8; // (4bytes = dynamic string (string len) + channelid.len + pubkey + 10xPubKey + u8) !!!!! (we must be more specific here!!! TODO)
-    let pda_channel_account_len: usize =
-        4 + open_token_core_data_decoded.channelid.len() + 32 + 1 + 1;
+    let oracle_addresses_len = 4 + 32 * open_token_core_data_decoded.oracles.len(); // Vec<Pubkey>
+    let pda_channel_account_len: usize = 4
+        + open_token_core_data_decoded.channelid.len()
+        + oracle_addresses_len
+        + 1
+        + 1
+        + 1
+        + 8
+        + 32
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 4
+        + MAX_CHECKPOINT_CID_LEN // checkpoint hash, sequence and cid (String, see checkpoint_processor)
+        + 32
+        + 8 // balances root and its sequence
+        + 32 // opener
+        + 8; // channel nonce
 
     // // Calculate rent required
     let pda_channel_rent = Rent::get()?;
@@ -208,7 +321,7 @@ pub fn open_channel(
     match invoke_signed(
         &system_instruction::create_account(
             msg_sender.key,
-            pda_channel_account.key, 
+            pda_channel_account.key,
             pda_channel_rent_lamports,
             pda_channel_account_len.try_into().unwrap(),
             program_id,
@@ -219,14 +332,14 @@ pub fn open_channel(
             system_program.clone(),
         ],
         &[&[
-            open_token_core_data_decoded.channelid.as_bytes().as_ref(),
+            open_token_core_data_decoded.channelid.as_bytes(),
             &[pda_channel_bump_seed],
         ]], // channel pda seed = channelid
     ) {
         Ok(()) => (),
         Err(_) => {
             msg!("Channel with provided 'channel_id' already exists!");
-            return Err(PaymentChannelError::Error.into());
+            return Err(PaymentChannelError::AccountAlreadyInitialized.into());
         }
     }
 
@@ -237,12 +350,72 @@ pub fn open_channel(
 
     // // ASSIGN VALUES
     pda_channel_account_data.channel_id = open_token_core_data_decoded.channelid;
-    pda_channel_account_data.oracle_address = oracle_account_data.oracle_address;
+    pda_channel_account_data.oracle_addresses = open_token_core_data_decoded.oracles;
+    pda_channel_account_data.oracle_threshold = open_token_core_data_decoded.oracle_threshold;
     pda_channel_account_data.current_status = 1; // 1 = OPENED
     pda_channel_account_data.num_of_active_stakeholders = 1;
+    pda_channel_account_data.sequence = open_token_core_data_decoded.sequence;
+    pda_channel_account_data.total_deposits = open_token_core_data_decoded.balance;
+    pda_channel_account_data.last_checkpoint_slot = Clock::get()?.slot;
+    pda_channel_account_data.emergency_timeout_slots =
+        open_token_core_data_decoded.emergency_timeout_slots;
+    pda_channel_account_data.mint = open_token_core_data_decoded.mint;
+    pda_channel_account_data.opener = *msg_sender.key;
+    pda_channel_account_data.channel_nonce = channel_nonce;
 
     pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
 
+    ChannelEvent::ChannelOpened {
+        channel_id: pda_channel_account_data.channel_id.clone(),
+        opener: *msg_sender.key,
+        mint: pda_channel_account_data.mint,
+        balance: open_token_core_data_decoded.balance,
+        sequence: open_token_core_data_decoded.sequence,
+    }
+    .emit();
+
+    if is_token_channel(&open_token_core_data_decoded.mint) {
+        let mint_account = next_account_info(account_info_iter)?;
+        let vault_account = next_account_info(account_info_iter)?;
+        let sender_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        check_token_program(token_program)?;
+
+        if *mint_account.key != open_token_core_data_decoded.mint {
+            msg!("Mint account not as defined in token");
+            return Err(PaymentChannelError::StateMismatch.into());
+        }
+
+        check_token_account(
+            sender_token_account,
+            &open_token_core_data_decoded.mint,
+            msg_sender.key,
+        )?;
+
+        create_vault(
+            program_id,
+            msg_sender,
+            vault_account,
+            mint_account,
+            pda_channel_account.key,
+            token_program,
+            system_program,
+        )?;
+
+        msg!("Transfering open amount (tokens) to channel vault");
+        deposit_to_vault(
+            sender_token_account,
+            vault_account,
+            msg_sender,
+            token_program,
+            open_token_core_data_decoded.balance,
+        )?;
+        msg!("Transfer completed, Channel Opened!");
+
+        return Ok(());
+    }
+
     // Send open_amount to new channel
     let open_amount_transfer = system_instruction::transfer(
         msg_sender.key,                       // From account
@@ -1,8 +1,229 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
 
+use crate::error::PaymentChannelError;
+use crate::try_from_slice_unchecked;
+use crate::verify_signature_processor::{load_ed25519_signatures, Ed25519SignatureEntry};
+
+// Max length (bytes) of the oracle name kept within the registry entry
+pub const MAX_ORACLE_NAME_LEN: usize = 32;
+
+// oracle_pda = (oracle_address); one registry entry per approved oracle, managed by the admin
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct OracleState {
     pub oracle_address: Pubkey,
-    pub oracle_status: bool,
+    pub oracle_status: bool,     // true = active, false = suspended
+    pub name: String,            // human readable name of the operator
+    pub endpoint_hash: [u8; 32], // hash of the off-chain endpoint (service URL) of the oracle
+    pub fee: u64,                // fee charged by the oracle (lamports / token units)
+    // Signing keys; `oracle_address` stays the identity channels are bound to
+    pub current_key: Pubkey,
+    pub previous_key: Pubkey,
+    pub previous_key_expiry_slot: u64, // previous key is accepted until this slot (exclusive)
+    pub key_version: u32,              // version of `current_key` (previous key = key_version - 1)
+}
+
+// Oracle signature within token payloads (defined by the token codec)
+pub use payment_channel_token::OracleSignature;
+
+impl OracleState {
+    // Suspended oracles (see modify_oracle) cannot sign off new channel states
+    pub fn check_active(&self) -> ProgramResult {
+        if !self.oracle_status {
+            msg!("Oracle {} is suspended", self.oracle_address);
+            return Err(PaymentChannelError::OracleInactive.into());
+        }
+
+        Ok(())
+    }
+
+    // Key that signed with `key_version`; previous key only until the handover deadline
+    pub fn signing_key(&self, key_version: u32, slot: u64) -> Result<Pubkey, ProgramError> {
+        if key_version == self.key_version {
+            return Ok(self.current_key);
+        }
+
+        if self.key_version > 0
+            && key_version == self.key_version - 1
+            && slot < self.previous_key_expiry_slot
+        {
+            return Ok(self.previous_key);
+        }
+
+        msg!(
+            "Key version {} of oracle {} is not accepted",
+            key_version,
+            self.oracle_address
+        );
+        Err(PaymentChannelError::OracleKeyNotAccepted.into())
+    }
+
+    // Whether one of `sig_oracles` is a signature of `message` by the current or previous key
+    // (handover deadline ignored - used to hold the oracle accountable for what it signed)
+    pub fn has_signed(
+        &self,
+        entries: &[Ed25519SignatureEntry],
+        message: &[u8],
+        sig_oracles: &[OracleSignature],
+    ) -> bool {
+        sig_oracles.iter().any(|sig_oracle| {
+            let key = if sig_oracle.key_version == self.key_version {
+                self.current_key
+            } else if self.key_version > 0 && sig_oracle.key_version == self.key_version - 1 {
+                self.previous_key
+            } else {
+                return false;
+            };
+
+            entries.iter().any(|entry| {
+                entry.pubkey == key
+                    && entry.message == message
+                    && entry.signature == sig_oracle.signature
+            })
+        })
+    }
+}
+
+// Max number of oracles a channel can be bound to
+pub const MAX_CHANNEL_ORACLES: usize = 5;
+
+// Oracle set of a channel; at least `threshold` distinct oracles of the set must sign a token
+pub struct OracleQuorum<'a> {
+    pub oracles: &'a [Pubkey],
+    pub threshold: u8,
+}
+
+impl OracleQuorum<'_> {
+    // 1 <= threshold <= number of oracles <= MAX_CHANNEL_ORACLES, no duplicates
+    pub fn check_config(&self) -> ProgramResult {
+        if self.threshold == 0
+            || self.threshold as usize > self.oracles.len()
+            || self.oracles.len() > MAX_CHANNEL_ORACLES
+        {
+            msg!(
+                "Invalid oracle set ({} of {})",
+                self.threshold,
+                self.oracles.len()
+            );
+            return Err(PaymentChannelError::InvalidOracleSet.into());
+        }
+
+        for (i, oracle) in self.oracles.iter().enumerate() {
+            if self.oracles[..i].contains(oracle) {
+                msg!("Oracle {} listed more than once", oracle);
+                return Err(PaymentChannelError::InvalidOracleSet.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    // `sig_oracles[i]` is the signature of the oracle behind `pda_oracle_accounts[i]`; every one
+    // must be a distinct oracle of the set with a matching Ed25519Program instruction.
+    // Suspended oracles are only accepted on exit paths (`allow_suspended`).
+    pub fn verify(
+        &self,
+        program_id: &Pubkey,
+        pda_oracle_accounts: &[AccountInfo],
+        sysvar_account: &AccountInfo,
+        message: &[u8],
+        sig_oracles: &[OracleSignature],
+        allow_suspended: bool,
+    ) -> ProgramResult {
+        if sig_oracles.len() != pda_oracle_accounts.len() {
+            msg!("Number of oracle signatures != number of oracle accounts");
+            return Err(PaymentChannelError::InvalidArgument.into());
+        }
+
+        let entries = load_ed25519_signatures(sysvar_account)?;
+        let clock = Clock::get()?;
+
+        let mut signers: Vec<Pubkey> = Vec::with_capacity(sig_oracles.len());
+        for (pda_oracle_account, sig_oracle) in pda_oracle_accounts.iter().zip(sig_oracles) {
+            let oracle_account_data = load_oracle(program_id, pda_oracle_account)?;
+
+            if !allow_suspended {
+                oracle_account_data.check_active()?;
+            }
+
+            let oracle = oracle_account_data.oracle_address;
+
+            if !self.oracles.contains(&oracle) {
+                msg!("Oracle {} is not an oracle of the channel", oracle);
+                return Err(PaymentChannelError::OracleNotInChannel.into());
+            }
+
+            if signers.contains(&oracle) {
+                msg!("Oracle {} signature provided more than once", oracle);
+                return Err(PaymentChannelError::InvalidOracleSignature.into());
+            }
+
+            let signing_key =
+                oracle_account_data.signing_key(sig_oracle.key_version, clock.slot)?;
+
+            if !entries.iter().any(|entry| {
+                entry.pubkey == signing_key
+                    && entry.message == message
+                    && entry.signature == sig_oracle.signature
+            }) {
+                msg!(
+                    "Verification of token signature (sig_oracle) of {} FAILED",
+                    oracle
+                );
+                return Err(PaymentChannelError::InvalidOracleSignature.into());
+            }
+
+            signers.push(oracle);
+        }
+
+        if signers.len() < self.threshold as usize {
+            msg!(
+                "Not enough oracle signatures ({} of {})",
+                signers.len(),
+                self.threshold
+            );
+            return Err(PaymentChannelError::NotEnoughOracleSignatures.into());
+        }
+
+        msg!("Oracle signatures succesfuly verified!");
+
+        Ok(())
+    }
+}
+
+// Load oracle account, which must be the oracle PDA created by this program
+pub fn load_oracle(
+    program_id: &Pubkey,
+    pda_oracle_account: &AccountInfo,
+) -> Result<OracleState, ProgramError> {
+    if pda_oracle_account.owner != program_id {
+        msg!("ORACLE account is not owned by the program");
+        return Err(PaymentChannelError::InvalidOwner.into());
+    }
+
+    let oracle_account_data =
+        match try_from_slice_unchecked::<OracleState>(&pda_oracle_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize ORACLE Account - wrong Oracle account provided");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    let (pda_oracle, _) =
+        Pubkey::find_program_address(&[oracle_account_data.oracle_address.as_ref()], program_id);
+
+    if pda_oracle != *pda_oracle_account.key {
+        msg!("pda_oracle != pda_oracle_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    Ok(oracle_account_data)
 }
@@ -0,0 +1,394 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use payment_channel_token::{TokenPayload, UpdateTokenData, ACTION_UPDATE};
+
+use crate::bond_state::{BondState, BOND_SEED, FRAUD_BOUNTY_PERCENT};
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::oracle_state::load_oracle;
+use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{check_vault, is_token_channel, vault_amount};
+use crate::try_from_slice_unchecked;
+use crate::verify_signature_processor::load_ed25519_signatures;
+
+fn decode_token(token_encoded: &[u8]) -> Result<(TokenPayload, UpdateTokenData), ProgramError> {
+    let token_decoded = match TokenPayload::try_from_slice(token_encoded) {
+        Ok(payload) => payload,
+        Err(_) => {
+            msg!("Failed to decode fraud proof token");
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
+        }
+    };
+
+    let token_core_data_decoded =
+        match UpdateTokenData::try_from_slice(token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode fraud proof token core data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    // Only off-chain states (action = 3) are checked
+    if token_core_data_decoded.action != ACTION_UPDATE {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    Ok((token_decoded, token_core_data_decoded))
+}
+
+// Same balance for every stakeholder, whatever the order of the (address, balance) pairs
+fn same_balances(first: &UpdateTokenData, second: &UpdateTokenData) -> bool {
+    let mut first_state = first.state.clone();
+    let mut second_state = second.state.clone();
+    first_state.sort();
+    second_state.sort();
+
+    first_state == second_state
+}
+
+// Prover gets FRAUD_BOUNTY_PERCENT of the slashed bond, the rest is split equally across the
+// stakeholders of the channel (first ones get the remainder, one lamport each)
+fn slashed_shares(slashed_amount: u64, num_of_stakeholders: usize) -> (u64, Vec<u64>) {
+    if num_of_stakeholders == 0 {
+        return (slashed_amount, Vec::new());
+    }
+
+    let bounty = (slashed_amount as u128 * FRAUD_BOUNTY_PERCENT as u128 / 100) as u64;
+    let rest = slashed_amount - bounty;
+
+    let share = rest / num_of_stakeholders as u64;
+    let remainder = (rest % num_of_stakeholders as u64) as usize;
+
+    let shares = (0..num_of_stakeholders)
+        .map(|i| share + (i < remainder) as u64)
+        .collect();
+
+    (bounty, shares)
+}
+
+// Proof is either
+// - two tokens of the same channel and sequence with conflicting balances, or
+// - one token (`second_token_encoded` empty) with balances summing to more than the channel holds
+// Bond of the oracle is slashed (bounty to the prover, rest to the stakeholders of the channel)
+// and the oracle is suspended
+pub fn prove_oracle_fraud(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    first_token_encoded: Vec<u8>,
+    second_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // prover (channel member)
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_prover_account = next_account_info(account_info_iter)?; // stakeholder PDA of msg_sender
+    let pda_oracle_account = next_account_info(account_info_iter)?; // accused oracle
+    let pda_bond_account = next_account_info(account_info_iter)?;
+    let sysvar_account = next_account_info(account_info_iter)?;
+    // Only for SPL Token denominated channels: vault_pda
+    // Remaining accounts: (pda_stakeholder, stakeholder address) for every stakeholder of the
+    // channel (active or with leave pending)
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (first_token_decoded, first_token_core_data_decoded) =
+        decode_token(first_token_encoded.as_slice())?;
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let channel_id = first_token_core_data_decoded.channelid.as_str();
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data.check_channel_nonce(first_token_core_data_decoded.channel_nonce)?;
+
+    // Load and check prover (must be member of the channel)
+    let (pda_prover, _) = Pubkey::find_program_address(
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
+        program_id,
+    );
+
+    if pda_prover != *pda_prover_account.key {
+        msg!("pda_prover != pda_prover_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_prover_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_prover_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    if pda_prover_account_data.status != 2 && pda_prover_account_data.status != 4 {
+        msg!("msg.sender is not part of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Load and check accused oracle and its bond
+    let mut oracle_account_data = load_oracle(program_id, pda_oracle_account)?;
+
+    if !pda_channel_account_data
+        .oracle_addresses
+        .contains(&oracle_account_data.oracle_address)
+    {
+        msg!("Oracle is not an oracle of the channel");
+        return Err(PaymentChannelError::OracleNotInChannel.into());
+    }
+
+    let (pda_bond, _) = Pubkey::find_program_address(
+        &[BOND_SEED, oracle_account_data.oracle_address.as_ref()],
+        program_id,
+    );
+
+    if pda_bond != *pda_bond_account.key {
+        msg!("pda_bond != pda_bond_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_bond_account_data =
+        match try_from_slice_unchecked::<BondState>(&pda_bond_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize BOND account");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_bond_account_data.amount == 0 {
+        msg!("Oracle bond already slashed");
+        return Err(PaymentChannelError::BondAlreadySlashed.into());
+    }
+
+    let entries = load_ed25519_signatures(sysvar_account)?;
+
+    if !oracle_account_data.has_signed(
+        &entries,
+        &first_token_decoded.encoded_data,
+        &first_token_decoded.sig_oracles,
+    ) {
+        msg!("First token is not signed by the oracle");
+        return Err(PaymentChannelError::InvalidFraudProof.into());
+    }
+
+    if second_token_encoded.is_empty() {
+        // Over-sum proof; only states after the latest on-chain action (e.g. withdrawal)
+        // must be covered by current deposits
+        if first_token_core_data_decoded.sequence <= pda_channel_account_data.sequence {
+            msg!("Token sequence is not greater than channel sequence");
+            return Err(PaymentChannelError::StaleSequence.into());
+        }
+
+        let total_amount = first_token_core_data_decoded
+            .state
+            .iter()
+            .fold(0u128, |total, (_, balance)| total + *balance as u128);
+
+        // Funds actually held by the channel (total_deposits is only as good as the oracle tokens)
+        let channel_funds = if is_token_channel(&pda_channel_account_data.mint) {
+            let vault_account = next_account_info(account_info_iter)?;
+            check_vault(program_id, pda_channel_account.key, vault_account)?;
+
+            vault_amount(vault_account)?
+        } else {
+            let rent_exempt_lamports = Rent::get()?.minimum_balance(pda_channel_account.data_len());
+
+            pda_channel_account
+                .lamports()
+                .saturating_sub(rent_exempt_lamports)
+        };
+
+        if total_amount <= channel_funds as u128 {
+            msg!("Token balances do not exceed channel funds");
+            return Err(PaymentChannelError::InvalidFraudProof.into());
+        }
+    } else {
+        // Conflict proof
+        let (second_token_decoded, second_token_core_data_decoded) =
+            decode_token(second_token_encoded.as_slice())?;
+
+        if second_token_core_data_decoded.channelid != first_token_core_data_decoded.channelid
+            || second_token_core_data_decoded.sequence != first_token_core_data_decoded.sequence
+            || second_token_core_data_decoded.channel_nonce
+                != first_token_core_data_decoded.channel_nonce
+        {
+            msg!("Tokens are not of the same channel (incarnation) and sequence");
+            return Err(PaymentChannelError::InvalidFraudProof.into());
+        }
+
+        if same_balances(
+            &first_token_core_data_decoded,
+            &second_token_core_data_decoded,
+        ) {
+            msg!("Token balances do not conflict");
+            return Err(PaymentChannelError::InvalidFraudProof.into());
+        }
+
+        if !oracle_account_data.has_signed(
+            &entries,
+            &second_token_decoded.encoded_data,
+            &second_token_decoded.sig_oracles,
+        ) {
+            msg!("Second token is not signed by the oracle");
+            return Err(PaymentChannelError::InvalidFraudProof.into());
+        }
+
+        if is_token_channel(&pda_channel_account_data.mint) {
+            next_account_info(account_info_iter)?; // vault_pda, not used by conflict proofs
+        }
+    }
+
+    // Stakeholders of the channel share the slashed bond, so all of them must be provided
+    let mut stakeholder_accounts = Vec::new();
+    for _ in 0..pda_channel_account_data.num_of_active_stakeholders {
+        let pda_stakeholder_account = next_account_info(account_info_iter)?;
+        let stakeholder_account = next_account_info(account_info_iter)?;
+
+        let (pda_stakeholder, _) = Pubkey::find_program_address(
+            &[channel_id.as_bytes(), stakeholder_account.key.as_ref()],
+            program_id,
+        );
+
+        if pda_stakeholder != *pda_stakeholder_account.key {
+            msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+            return Err(PaymentChannelError::InvalidSeeds.into());
+        }
+
+        if stakeholder_accounts
+            .iter()
+            .any(|account: &&AccountInfo| account.key == stakeholder_account.key)
+        {
+            msg!(
+                "Stakeholder {} provided more than once",
+                stakeholder_account.key
+            );
+            return Err(PaymentChannelError::InvalidArgument.into());
+        }
+
+        let pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+            &pda_stakeholder_account.data.borrow(),
+        ) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!(
+                    "Failed to deserialize Stakeholder PDA data of {}",
+                    stakeholder_account.key
+                );
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+        if pda_stakeholder_account_data.status != 2 && pda_stakeholder_account_data.status != 4 {
+            msg!(
+                "Stakeholder {} is not part of the channel",
+                stakeholder_account.key
+            );
+            return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+        }
+
+        stakeholder_accounts.push(stakeholder_account);
+    }
+
+    // Slash bond and suspend the oracle
+    let slashed_amount = pda_bond_account_data.amount;
+
+    pda_bond_account_data.amount = 0;
+    pda_bond_account_data.serialize(&mut &mut pda_bond_account.data.borrow_mut()[..])?;
+
+    oracle_account_data.oracle_status = false;
+    oracle_account_data.serialize(&mut &mut pda_oracle_account.data.borrow_mut()[..])?;
+
+    let (bounty, shares) = slashed_shares(slashed_amount, stakeholder_accounts.len());
+
+    msg!(
+        "Oracle {} fraud proven, {} lamports slashed ({} to {}, rest to the stakeholders)",
+        oracle_account_data.oracle_address,
+        slashed_amount,
+        bounty,
+        msg_sender.key
+    );
+
+    **pda_bond_account.try_borrow_mut_lamports()? -= slashed_amount;
+    **msg_sender.try_borrow_mut_lamports()? += bounty;
+    for (stakeholder_account, share) in stakeholder_accounts.iter().zip(shares) {
+        **stakeholder_account.try_borrow_mut_lamports()? += share;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update_token(state: Vec<(Pubkey, u64)>) -> UpdateTokenData {
+        UpdateTokenData {
+            action: ACTION_UPDATE,
+            channelid: "channel".to_string(),
+            num_of_stakeholders: state.len() as u8,
+            state,
+            sender: Pubkey::default(),
+            sequence: 1,
+            channel_nonce: 1,
+            sig_sender: [0; 64],
+        }
+    }
+
+    #[test]
+    fn reordered_state_does_not_conflict() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let first = update_token(vec![(alice, 10), (bob, 20)]);
+        let second = update_token(vec![(bob, 20), (alice, 10)]);
+        assert!(same_balances(&first, &second));
+
+        let third = update_token(vec![(bob, 10), (alice, 20)]);
+        assert!(!same_balances(&first, &third));
+    }
+
+    #[test]
+    fn slashed_bond_is_split_with_capped_bounty() {
+        assert_eq!(slashed_shares(1_000, 3), (100, vec![300, 300, 300]));
+        assert_eq!(slashed_shares(1_000, 4), (100, vec![225, 225, 225, 225]));
+        assert_eq!(slashed_shares(101, 2), (10, vec![46, 45]));
+
+        let (bounty, shares) = slashed_shares(u64::MAX, 7);
+        assert_eq!(bounty + shares.iter().sum::<u64>(), u64::MAX);
+    }
+}
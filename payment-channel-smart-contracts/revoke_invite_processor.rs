@@ -0,0 +1,180 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::channel_state::ChannelState;
+use crate::close_account::close_stakeholder;
+use crate::error::PaymentChannelError;
+use crate::stakeholder_state::StakeholderState;
+use crate::try_from_slice_unchecked;
+
+// Load pending invitation (stakeholder PDA with status 1 = invited) of `invitee`
+fn load_invitation(
+    program_id: &Pubkey,
+    channel_id: &str,
+    invitee: &Pubkey,
+    pda_invitee_account: &AccountInfo,
+) -> Result<StakeholderState, ProgramError> {
+    let (pda_invitee, _) =
+        Pubkey::find_program_address(&[channel_id.as_bytes(), invitee.as_ref()], program_id);
+
+    if pda_invitee != *pda_invitee_account.key {
+        msg!("pda_invitee != pda_invitee_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_invitee_account_data =
+        match try_from_slice_unchecked::<StakeholderState>(&pda_invitee_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize invitee Stakeholder PDA data");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_invitee_account_data.status != 1 {
+        msg!("Stakeholder status != invited");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    Ok(pda_invitee_account_data)
+}
+
+// Inviter (or the channel opener) withdraws an invitation that was not accepted yet
+pub fn revoke_invite(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    channel_id: String,
+    invitee: Pubkey,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // inviter or channel opener
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_invitee_account = next_account_info(account_info_iter)?;
+    let rent_payer_account = next_account_info(account_info_iter)?; // inviter
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let pda_invitee_account_data =
+        load_invitation(program_id, &channel_id, &invitee, pda_invitee_account)?;
+
+    // Channel data is only needed if the opener revokes someone else's invitation
+    // (the inviter can revoke even once the channel has been closed, and once it is closed anyone
+    // can - rent goes back to the inviter either way)
+    let channel_closed =
+        pda_channel_account.lamports() == 0 || pda_channel_account.owner != program_id;
+
+    if *msg_sender.key != pda_invitee_account_data.rent_payer && !channel_closed {
+        let pda_channel_account_data =
+            match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+                Ok(data) => data,
+                Err(_) => {
+                    msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                    return Err(PaymentChannelError::AccountNotInitialized.into());
+                }
+            };
+
+        if pda_channel_account_data.channel_id != channel_id
+            || *msg_sender.key != pda_channel_account_data.opener
+        {
+            msg!(
+                "Signer {} is neither the inviter nor the channel opener",
+                msg_sender.key
+            );
+            return Err(PaymentChannelError::Unauthorized.into());
+        }
+    }
+
+    close_stakeholder(
+        pda_invitee_account,
+        &pda_invitee_account_data,
+        rent_payer_account,
+    )?;
+
+    msg!("Invitation of {} revoked", invitee);
+
+    Ok(())
+}
+
+// Invitee turns the invitation down
+pub fn decline_invite(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    channel_id: String,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?; // invitee
+    let pda_invitee_account = next_account_info(account_info_iter)?;
+    let rent_payer_account = next_account_info(account_info_iter)?; // inviter
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let pda_invitee_account_data =
+        load_invitation(program_id, &channel_id, msg_sender.key, pda_invitee_account)?;
+
+    close_stakeholder(
+        pda_invitee_account,
+        &pda_invitee_account_data,
+        rent_payer_account,
+    )?;
+
+    msg!("Invitation of {} declined", msg_sender.key);
+
+    Ok(())
+}
+
+// Close invitations still pending when the channel is closed; remaining accounts are
+// (pda_invitee, rent payer of pda_invitee) pairs
+pub fn close_invitations<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    program_id: &Pubkey,
+    channel_id: &str,
+    account_info_iter: &mut I,
+) -> ProgramResult {
+    while let Some(pda_invitee_account) = account_info_iter.next() {
+        let rent_payer_account = next_account_info(account_info_iter)?;
+
+        let invitee = match try_from_slice_unchecked::<StakeholderState>(
+            &pda_invitee_account.data.borrow(),
+        ) {
+            Ok(data) => data.stakeholder_address,
+            Err(_) => {
+                msg!("Failed to deserialize invitee Stakeholder PDA data");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+        let pda_invitee_account_data =
+            load_invitation(program_id, channel_id, &invitee, pda_invitee_account)?;
+
+        close_stakeholder(
+            pda_invitee_account,
+            &pda_invitee_account_data,
+            rent_payer_account,
+        )?;
+
+        msg!("Invitation of {} closed", invitee);
+    }
+
+    Ok(())
+}
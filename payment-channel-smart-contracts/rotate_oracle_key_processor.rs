@@ -0,0 +1,61 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
+};
+
+use crate::config_state::check_admin;
+use crate::error::PaymentChannelError;
+use crate::oracle_state::load_oracle;
+
+pub fn rotate_oracle_key(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_key: Pubkey,
+    handover_slots: u64,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let admin = next_account_info(account_info_iter)?; // msg.sender
+    let pda_oracle_account = next_account_info(account_info_iter)?;
+    let pda_config_account = next_account_info(account_info_iter)?;
+
+    check_admin(program_id, admin, pda_config_account)?;
+
+    let mut oracle_account_data = load_oracle(program_id, pda_oracle_account)?;
+
+    if new_key == Pubkey::default() || new_key == oracle_account_data.current_key {
+        msg!("Invalid new oracle key");
+        return Err(PaymentChannelError::InvalidArgument.into());
+    }
+
+    let clock = Clock::get()?;
+
+    // Tokens signed by the current key keep settling until the handover deadline
+    oracle_account_data.previous_key = oracle_account_data.current_key;
+    oracle_account_data.previous_key_expiry_slot = clock
+        .slot
+        .checked_add(handover_slots)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+    oracle_account_data.current_key = new_key;
+    oracle_account_data.key_version = oracle_account_data
+        .key_version
+        .checked_add(1)
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    oracle_account_data.serialize(&mut &mut pda_oracle_account.data.borrow_mut()[..])?;
+
+    msg!(
+        "Oracle {} key rotated to version {}, previous key valid until slot {}",
+        oracle_account_data.oracle_address,
+        oracle_account_data.key_version,
+        oracle_account_data.previous_key_expiry_slot
+    );
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use payment_channel_token::{TokenPayload, UpdateTokenData, ACTION_UPDATE};
+
+use crate::channel_state::ChannelState;
+use crate::close_account::{close_channel, close_stakeholder};
+use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
+use crate::revoke_invite_processor::close_invitations;
+use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{
+    check_token_account, check_token_program, check_vault, is_token_channel, vault_amount,
+    withdraw_from_vault,
+};
+use crate::try_from_slice_unchecked;
+
+pub fn settle_channel(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    settle_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?;
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let sysvar_account = next_account_info(account_info_iter)?;
+    let opener_account = next_account_info(account_info_iter)?; // paid the channel PDA rent
+                                                                // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+                                                                // Only for SPL Token denominated channels: vault_pda, token program
+                                                                // Remaining accounts: (pda_stakeholder, stakeholder address, rent payer of pda_stakeholder)
+                                                                // for every stakeholder listed in the token, in the same order as in the token
+                                                                // (stakeholder token account instead of address for SPL Token denominated channels)
+                                                                // followed by (pda_invitee, rent payer of pda_invitee) for pending invitations
+
+    if !msg_sender.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let settle_token_decoded = match TokenPayload::try_from_slice(settle_token_encoded.as_slice()) {
+        Ok(payload) => payload,
+        Err(_) => {
+            msg!("Failed to decode settle_token");
+            return Err(PaymentChannelError::InvalidTokenEncoding.into());
+        }
+    };
+
+    let settle_token_core_data_decoded =
+        match UpdateTokenData::try_from_slice(settle_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode settle_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    // Only off-chain state (action = 3) can be settled
+    if settle_token_core_data_decoded.action != ACTION_UPDATE {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let channel_id = settle_token_core_data_decoded.channelid.as_str();
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("settle_token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data.check_channel_nonce(settle_token_core_data_decoded.channel_nonce)?;
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    if settle_token_core_data_decoded.sequence <= pda_channel_account_data.sequence {
+        msg!(
+            "Token sequence {} is not greater than channel sequence {}",
+            settle_token_core_data_decoded.sequence,
+            pda_channel_account_data.sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    // Token must not be older than (or conflict with) the checkpointed state
+    pda_channel_account_data.check_checkpoint(
+        settle_token_core_data_decoded.sequence,
+        &settle_token_decoded.encoded_data,
+    )?;
+
+    // Every stakeholder still in the channel must be paid out
+    if settle_token_core_data_decoded.num_of_stakeholders
+        != pda_channel_account_data.num_of_active_stakeholders
+    {
+        msg!("Token does not include all active stakeholders of the channel");
+        return Err(PaymentChannelError::StateMismatch.into());
+    }
+
+    // Settlement is triggered by one of the stakeholders (must be active, checked below)
+    if settle_token_core_data_decoded
+        .balance_of(msg_sender.key)
+        .is_none()
+    {
+        msg!("msg.sender is not part of the provided state");
+        return Err(PaymentChannelError::StateMismatch.into());
+    }
+
+    let total_amount = settle_token_core_data_decoded
+        .state
+        .iter()
+        .try_fold(0u64, |total, (_, balance)| total.checked_add(*balance))
+        .ok_or(PaymentChannelError::ArithmeticOverflow)?;
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, settle_token_decoded.sig_oracles.len())?;
+
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        settle_token_decoded.oracle_preimage(),
+        &settle_token_decoded.sig_oracles,
+        false,
+    )?;
+
+    // Funds held by the channel, apart from the rent of the channel PDA (refunded to the opener)
+    let (token_accounts, channel_funds) = if is_token_channel(&pda_channel_account_data.mint) {
+        let vault_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        check_token_program(token_program)?;
+        check_vault(program_id, pda_channel_account.key, vault_account)?;
+
+        (
+            Some((vault_account, token_program)),
+            vault_amount(vault_account)?,
+        )
+    } else {
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(pda_channel_account.data_len());
+
+        (
+            None,
+            pda_channel_account
+                .lamports()
+                .saturating_sub(rent_exempt_lamports),
+        )
+    };
+
+    if channel_funds < total_amount {
+        msg!("Channel does not hold enough funds to settle provided state");
+        return Err(PaymentChannelError::InsufficientFunds.into());
+    }
+
+    // Funds above the settled state are not left to the opener but paid out with the balances
+    let surplus = channel_funds - total_amount;
+    let balances: Vec<u64> = settle_token_core_data_decoded
+        .state
+        .iter()
+        .map(|(_, balance)| *balance)
+        .collect();
+    let surplus_shares = surplus_shares(&balances, surplus);
+
+    if surplus > 0 {
+        msg!("Surplus of {} distributed across the stakeholders", surplus);
+    }
+
+    let mut msg_sender_active = false;
+
+    for ((address, balance), surplus_share) in settle_token_core_data_decoded
+        .state
+        .iter()
+        .zip(surplus_shares)
+    {
+        let pda_stakeholder_account = next_account_info(account_info_iter)?;
+        let stakeholder_account = next_account_info(account_info_iter)?;
+        let rent_payer_account = next_account_info(account_info_iter)?;
+
+        let (pda_stakeholder, _) =
+            Pubkey::find_program_address(&[channel_id.as_bytes(), address.as_ref()], program_id);
+
+        if pda_stakeholder != *pda_stakeholder_account.key {
+            msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+            return Err(PaymentChannelError::InvalidSeeds.into());
+        }
+
+        if token_accounts.is_some() {
+            check_token_account(stakeholder_account, &pda_channel_account_data.mint, address)?;
+        } else if *stakeholder_account.key != *address {
+            msg!("Stakeholder account {} not as defined in token", address);
+            return Err(PaymentChannelError::StateMismatch.into());
+        }
+
+        let pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+            &pda_stakeholder_account.data.borrow(),
+        ) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserialize Stakeholder PDA data of {}", address);
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+        // Active or with leave pending (PDA is closed right away, so duplicates fail here)
+        if pda_stakeholder_account_data.status != 2 && pda_stakeholder_account_data.status != 4 {
+            msg!("Stakeholder {} is not part of the channel", address);
+            return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+        }
+
+        if *address == *msg_sender.key {
+            msg_sender_active = pda_stakeholder_account_data.status == 2;
+        }
+
+        // Stakeholder is out of the channel (status 3 = inactive) - PDA is closed, rent refunded
+        close_stakeholder(
+            pda_stakeholder_account,
+            &pda_stakeholder_account_data,
+            rent_payer_account,
+        )?;
+
+        let amount = balance + surplus_share;
+
+        msg!("amount to be transfered to {}: {}", address, amount);
+
+        match token_accounts {
+            Some((vault_account, token_program)) => withdraw_from_vault(
+                program_id,
+                channel_id,
+                vault_account,
+                stakeholder_account,
+                pda_channel_account,
+                token_program,
+                amount,
+            )?,
+            None => {
+                **pda_channel_account.try_borrow_mut_lamports()? -= amount;
+                **stakeholder_account.try_borrow_mut_lamports()? += amount;
+            }
+        }
+
+        ChannelEvent::StakeholderLeft {
+            channel_id: channel_id.to_string(),
+            stakeholder: *address,
+            amount,
+        }
+        .emit();
+    }
+
+    if !msg_sender_active {
+        msg!("msg.sender is not an active stakeholder of the channel");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Invitations nobody accepted would otherwise outlive the channel
+    close_invitations(program_id, channel_id, account_info_iter)?;
+
+    // ASSIGN VALUES
+    pda_channel_account_data.num_of_active_stakeholders = 0;
+    pda_channel_account_data.total_deposits = 0;
+    pda_channel_account_data.current_status = 2; // 1 = OPENED // 2 = CLOSED
+    pda_channel_account_data.sequence = settle_token_core_data_decoded.sequence;
+
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    msg!("Channel settled and closed!");
+
+    close_channel(
+        program_id,
+        pda_channel_account,
+        &pda_channel_account_data,
+        opener_account,
+        token_accounts,
+    )?;
+
+    Ok(())
+}
+
+// Splits `surplus` pro rata to `balances` (equally if all of them are 0); shares are taken from
+// the cumulative split, so they add up to `surplus` exactly. Balances must sum up to a u64.
+fn surplus_shares(balances: &[u64], surplus: u64) -> Vec<u64> {
+    let total: u128 = balances.iter().map(|balance| *balance as u128).sum();
+    let (weights, total): (Vec<u128>, u128) = if total == 0 {
+        (vec![1; balances.len()], balances.len() as u128)
+    } else {
+        (
+            balances.iter().map(|balance| *balance as u128).collect(),
+            total,
+        )
+    };
+
+    let mut shares = Vec::with_capacity(balances.len());
+    let mut cumulative_weight = 0u128;
+    let mut distributed = 0u64;
+
+    for weight in weights {
+        cumulative_weight += weight;
+        // <= surplus, as cumulative_weight <= total
+        let cumulative_share = (surplus as u128 * cumulative_weight / total) as u64;
+        shares.push(cumulative_share - distributed);
+        distributed = cumulative_share;
+    }
+
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surplus_is_split_pro_rata_and_exactly() {
+        assert_eq!(surplus_shares(&[100, 300], 40), vec![10, 30]);
+        assert_eq!(surplus_shares(&[1, 1, 1], 10), vec![3, 3, 4]);
+        assert_eq!(surplus_shares(&[5, 0, 5], 3), vec![1, 0, 2]);
+        assert_eq!(surplus_shares(&[7, 9], 0), vec![0, 0]);
+    }
+
+    #[test]
+    fn surplus_is_split_equally_without_balances() {
+        assert_eq!(surplus_shares(&[0, 0], 5), vec![2, 3]);
+    }
+
+    #[test]
+    fn surplus_shares_do_not_overflow() {
+        let shares = surplus_shares(&[u64::MAX - 1, 1], u64::MAX);
+        assert_eq!(shares, vec![u64::MAX - 1, 1]);
+    }
+}
@@ -2,9 +2,13 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 
 // stakeholder_pda = (channelid+publickey)
+// status codes: 0 = UNDEFINED; 1 = INVITED; 2 = ACTIVE; 3 = INACTIVE; 4 = CLOSING (leave pending)
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct StakeholderState {
     pub stakeholder_address: Pubkey, // address
     pub balance: u64,                // balance
-    pub status: u8,                  //  status codes: 0 = UNDEFINED; 1 = INVITED; 2 = ACTIVE;
+    pub status: u8,                  // status
+    pub closing_slot: u64,           // slot from which a pending leave can be finalized
+    pub sequence: u64,               // sequence of the token the balance is based on
+    pub rent_payer: Pubkey,          // paid the PDA rent (opener / inviter), refunded on close
 }
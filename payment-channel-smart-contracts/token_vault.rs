@@ -0,0 +1,218 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::error::PaymentChannelError;
+
+// vault_pda = ("vault"+channel_pda); SPL Token account owned by the channel PDA
+pub const VAULT_SEED: &[u8] = b"vault";
+
+// Channels opened with the default mint are denominated in native lamports
+pub fn is_token_channel(mint: &Pubkey) -> bool {
+    *mint != Pubkey::default()
+}
+
+pub fn check_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if *token_program.key != spl_token::id() {
+        msg!("Wrong token program provided");
+        return Err(PaymentChannelError::InvalidTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_vault(
+    program_id: &Pubkey,
+    pda_channel: &Pubkey,
+    vault_account: &AccountInfo,
+) -> Result<u8, PaymentChannelError> {
+    let (pda_vault, pda_vault_bump_seed) =
+        Pubkey::find_program_address(&[VAULT_SEED, pda_channel.as_ref()], program_id);
+
+    if pda_vault != *vault_account.key {
+        msg!("pda_vault != vault_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds);
+    }
+
+    Ok(pda_vault_bump_seed)
+}
+
+// Token account must hold `mint` and belong to `owner`
+pub fn check_token_account(
+    token_account: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> ProgramResult {
+    if *token_account.owner != spl_token::id() {
+        msg!("Token account is not owned by the token program");
+        return Err(PaymentChannelError::InvalidTokenAccount.into());
+    }
+
+    let token_account_data = spl_token::state::Account::unpack(&token_account.data.borrow())?;
+
+    if token_account_data.mint != *mint || token_account_data.owner != *owner {
+        msg!("Token account mint/owner mismatch");
+        return Err(PaymentChannelError::InvalidTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+pub fn vault_amount(vault_account: &AccountInfo) -> Result<u64, PaymentChannelError> {
+    match spl_token::state::Account::unpack(&vault_account.data.borrow()) {
+        Ok(data) => Ok(data.amount),
+        Err(_) => {
+            msg!("Failed to unpack vault account");
+            Err(PaymentChannelError::InvalidTokenAccount)
+        }
+    }
+}
+
+// Create vault_pda and initialize it as token account of `mint` with the channel PDA as authority
+pub fn create_vault<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    pda_channel: &Pubkey,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let pda_vault_bump_seed = check_vault(program_id, pda_channel, vault_account)?;
+
+    let vault_account_len = spl_token::state::Account::LEN;
+    let vault_rent_lamports = Rent::get()?.minimum_balance(vault_account_len);
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            vault_account.key,
+            vault_rent_lamports,
+            vault_account_len.try_into().unwrap(),
+            &spl_token::id(),
+        ),
+        &[payer.clone(), vault_account.clone(), system_program.clone()],
+        &[&[VAULT_SEED, pda_channel.as_ref(), &[pda_vault_bump_seed]]], // vault pda seed = "vault"+channel_pda
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            vault_account.key,
+            mint_account.key,
+            pda_channel,
+        )?,
+        &[
+            vault_account.clone(),
+            mint_account.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+// Move `amount` tokens from stakeholder's token account into the vault (signed by stakeholder)
+pub fn deposit_to_vault<'a>(
+    source_token_account: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            source_token_account.key,
+            vault_account.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[
+            source_token_account.clone(),
+            vault_account.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+    )
+}
+
+// Move `amount` tokens out of the vault (signed by the channel PDA)
+pub fn withdraw_from_vault<'a>(
+    program_id: &Pubkey,
+    channel_id: &str,
+    vault_account: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
+    pda_channel_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let (pda_channel, pda_channel_bump_seed) =
+        Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            vault_account.key,
+            destination_token_account.key,
+            pda_channel_account.key,
+            &[],
+            amount,
+        )?,
+        &[
+            vault_account.clone(),
+            destination_token_account.clone(),
+            pda_channel_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[channel_id.as_bytes(), &[pda_channel_bump_seed]]],
+    )
+}
+
+// Close the (empty) vault, rent goes to `destination` (signed by the channel PDA)
+pub fn close_vault<'a>(
+    program_id: &Pubkey,
+    channel_id: &str,
+    vault_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    pda_channel_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (pda_channel, pda_channel_bump_seed) =
+        Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            &spl_token::id(),
+            vault_account.key,
+            destination.key,
+            pda_channel_account.key,
+            &[],
+        )?,
+        &[
+            vault_account.clone(),
+            destination.clone(),
+            pda_channel_account.clone(),
+            token_program.clone(),
+        ],
+        &[&[channel_id.as_bytes(), &[pda_channel_bump_seed]]],
+    )
+}
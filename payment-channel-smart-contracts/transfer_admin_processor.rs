@@ -0,0 +1,69 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::config_state::{check_admin, load_config};
+use crate::error::PaymentChannelError;
+
+// Step 1: current admin proposes `new_admin` (Pubkey::default() cancels a pending handover)
+pub fn transfer_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_admin: Pubkey,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let admin = next_account_info(account_info_iter)?; // msg.sender
+    let pda_config_account = next_account_info(account_info_iter)?;
+
+    check_admin(program_id, admin, pda_config_account)?;
+
+    let mut pda_config_account_data = load_config(program_id, pda_config_account)?;
+
+    pda_config_account_data.pending_admin = new_admin;
+
+    pda_config_account_data.serialize(&mut &mut pda_config_account.data.borrow_mut()[..])?;
+
+    msg!("Admin handover proposed to {}", new_admin);
+
+    Ok(())
+}
+
+// Step 2: proposed admin accepts and becomes the admin
+pub fn accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let pending_admin = next_account_info(account_info_iter)?; // msg.sender
+    let pda_config_account = next_account_info(account_info_iter)?;
+
+    let mut pda_config_account_data = load_config(program_id, pda_config_account)?;
+
+    if !pending_admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if pda_config_account_data.pending_admin == Pubkey::default()
+        || *pending_admin.key != pda_config_account_data.pending_admin
+    {
+        msg!("Signer {} is not the pending admin", pending_admin.key);
+        return Err(PaymentChannelError::Unauthorized.into());
+    }
+
+    pda_config_account_data.admin = pda_config_account_data.pending_admin;
+    pda_config_account_data.pending_admin = Pubkey::default();
+
+    pda_config_account_data.serialize(&mut &mut pda_config_account.data.borrow_mut()[..])?;
+
+    msg!("Admin handover completed, admin: {}", pending_admin.key);
+
+    Ok(())
+}
@@ -1,97 +1,177 @@
 use crate::AccountInfo;
 /// This mod contains functions that validate that an instruction
 /// is constructed the way we expect. In this case, this is for
-/// `Ed25519Program.createInstructionWithPublicKey()` and
-/// `Secp256k1Program.createInstructionWithEthAddress()` instructions.
+/// `Ed25519Program.createInstructionWithPublicKey()` instructions,
+/// placed anywhere in the transaction and carrying one or more signatures.
 use solana_program::ed25519_program::ID as ED25519_ID;
 use solana_program::entrypoint::ProgramResult;
-use solana_program::instruction::Instruction;
 use solana_program::msg;
+use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
-use solana_program::sysvar::instructions::load_instruction_at_checked;
+use solana_program::sysvar::instructions::{load_instruction_at_checked, ID as INSTRUCTIONS_ID};
 
 use crate::error::PaymentChannelError;
 
+// 2*u8 (num_signatures + padding)
+const SIGNATURE_OFFSETS_START: usize = 2;
+// 7*u16 (see Ed25519SignatureOffsets in solana-sdk)
+const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+// Offsets point into the Ed25519Program instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Signature checked by the Ed25519Program within the current transaction
+#[derive(Debug, PartialEq)]
+pub struct Ed25519SignatureEntry {
+    pub instruction_index: u16, // Ed25519Program instruction that carries the signature
+    pub pubkey: Pubkey,
+    pub message: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+/// Verify that `pubkey` signed `message` with `sig`, i.e. that one of the Ed25519Program
+/// instructions of the current transaction (at any index) checked exactly this signature
 pub fn verify_ed25519(
     sysvar_account: &AccountInfo,
-    //pubkey: [u8; 32], // originaly
-    pubkey: Pubkey, // customized
+    pubkey: Pubkey,
     message: Vec<u8>,
     sig: [u8; 64],
 ) -> ProgramResult {
-    let ix: Instruction = load_instruction_at_checked(0, sysvar_account)?;
+    let entries = load_ed25519_signatures(sysvar_account)?;
 
-    // Check that ix is what we expect to have been sent
-    verify_ed25519_ix(&ix, &pubkey.as_ref(), &message, &sig)?;
+    if !entries
+        .iter()
+        .any(|entry| entry.pubkey == pubkey && entry.message == message && entry.signature == sig)
+    {
+        msg!("No Ed25519Program signature found for {}", pubkey);
+        return Err(PaymentChannelError::SignatureNotFound.into());
+    }
 
     Ok(())
 }
 
-/// Verify Ed25519Program instruction fields
-pub fn verify_ed25519_ix(ix: &Instruction, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
-    if ix.program_id       != ED25519_ID                   ||  // The program id we expect
-            ix.accounts.len()   != 0                            ||  // With no context accounts
-            ix.data.len()       != (16 + 64 + 32 + msg.len())
-    // And data of this size
-    {
-        msg!("verify_ed25519_ix error");
-        return Err(PaymentChannelError::Error.into());
+/// Load every signature entry of every Ed25519Program instruction within the current transaction
+pub fn load_ed25519_signatures(
+    sysvar_account: &AccountInfo,
+) -> Result<Vec<Ed25519SignatureEntry>, ProgramError> {
+    if *sysvar_account.key != INSTRUCTIONS_ID {
+        msg!("Wrong instructions sysvar account provided");
+        return Err(ProgramError::UnsupportedSysvar);
     }
 
-    check_ed25519_data(&ix.data, pubkey, msg, sig)?; // If that's not the case, check data
+    // First 2 bytes of the instructions sysvar = number of instructions within the transaction
+    let num_instructions = {
+        let data = sysvar_account.try_borrow_data()?;
+        if data.len() < 2 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        u16::from_le_bytes([data[0], data[1]])
+    };
 
-    Ok(())
+    let mut entries = Vec::new();
+    for index in 0..num_instructions {
+        let ix = load_instruction_at_checked(index as usize, sysvar_account)?;
+
+        if ix.program_id != ED25519_ID {
+            continue;
+        }
+
+        entries.extend(parse_ed25519_instruction(sysvar_account, index, &ix.data)?);
+    }
+
+    Ok(entries)
 }
 
-/// Verify serialized Ed25519Program instruction data
-pub fn check_ed25519_data(data: &[u8], pubkey: &[u8], msg: &[u8], sig: &[u8]) -> ProgramResult {
+/// Parse serialized Ed25519Program instruction data (all signatures it carries)
+pub fn parse_ed25519_instruction(
+    sysvar_account: &AccountInfo,
+    instruction_index: u16,
+    data: &[u8],
+) -> Result<Vec<Ed25519SignatureEntry>, ProgramError> {
     // According to this layout used by the Ed25519Program
     // https://github.com/solana-labs/solana-web3.js/blob/master/src/ed25519-program.ts#L33
+    // https://github.com/solana-labs/solana/blob/master/sdk/src/ed25519_instruction.rs
 
-    // "Deserializing" byte slices
-
-    let num_signatures = &[data[0]]; // Byte  0
-    let padding = &[data[1]]; // Byte  1
-    let signature_offset = &data[2..=3]; // Bytes 2,3
-    let signature_instruction_index = &data[4..=5]; // Bytes 4,5
-    let public_key_offset = &data[6..=7]; // Bytes 6,7
-    let public_key_instruction_index = &data[8..=9]; // Bytes 8,9
-    let message_data_offset = &data[10..=11]; // Bytes 10,11
-    let message_data_size = &data[12..=13]; // Bytes 12,13
-    let message_instruction_index = &data[14..=15]; // Bytes 14,15
-
-    let data_pubkey = &data[16..16 + 32]; // Bytes 16..16+32
-    let data_sig = &data[48..48 + 64]; // Bytes 48..48+64
-    let data_msg = &data[112..]; // Bytes 112..end
-
-    // Expected values
-
-    let exp_public_key_offset: u16 = 16; // 2*u8 + 7*u16
-    let exp_signature_offset: u16 = exp_public_key_offset + pubkey.len() as u16;
-    let exp_message_data_offset: u16 = exp_signature_offset + sig.len() as u16;
-    let exp_num_signatures: u8 = 1;
-    let exp_message_data_size: u16 = msg.len().try_into().unwrap();
-
-    // Header and Arg Checks
-
-    // Header
-    if num_signatures != &exp_num_signatures.to_le_bytes()
-        || padding != &[0]
-        || signature_offset != &exp_signature_offset.to_le_bytes()
-        || signature_instruction_index != &u16::MAX.to_le_bytes()
-        || public_key_offset != &exp_public_key_offset.to_le_bytes()
-        || public_key_instruction_index != &u16::MAX.to_le_bytes()
-        || message_data_offset != &exp_message_data_offset.to_le_bytes()
-        || message_data_size != &exp_message_data_size.to_le_bytes()
-        || message_instruction_index != &u16::MAX.to_le_bytes()
-    {
-        return Err(PaymentChannelError::Error.into());
+    if data.len() < SIGNATURE_OFFSETS_START {
+        msg!("Ed25519Program instruction data too short");
+        return Err(PaymentChannelError::InvalidEd25519Instruction.into());
     }
 
-    // Arguments
-    if data_pubkey != pubkey || data_msg != msg || data_sig != sig {
-        return Err(PaymentChannelError::Error.into());
+    let num_signatures = data[0] as usize; // Byte  0 (Byte 1 = padding)
+
+    let mut entries = Vec::with_capacity(num_signatures);
+    for i in 0..num_signatures {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        let offsets = data
+            .get(start..start + SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .ok_or_else(|| {
+                msg!("Ed25519Program signature offsets out of bounds");
+                ProgramError::from(PaymentChannelError::InvalidEd25519Instruction)
+            })?;
+
+        let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+        let signature_offset = read_u16(0); // Bytes 0,1
+        let signature_instruction_index = read_u16(2); // Bytes 2,3
+        let public_key_offset = read_u16(4); // Bytes 4,5
+        let public_key_instruction_index = read_u16(6); // Bytes 6,7
+        let message_data_offset = read_u16(8); // Bytes 8,9
+        let message_data_size = read_u16(10); // Bytes 10,11
+        let message_instruction_index = read_u16(12); // Bytes 12,13
+
+        let signature = load_ed25519_field(
+            sysvar_account,
+            data,
+            signature_instruction_index,
+            signature_offset,
+            64,
+        )?;
+        let pubkey = load_ed25519_field(
+            sysvar_account,
+            data,
+            public_key_instruction_index,
+            public_key_offset,
+            32,
+        )?;
+        let message = load_ed25519_field(
+            sysvar_account,
+            data,
+            message_instruction_index,
+            message_data_offset,
+            message_data_size,
+        )?;
+
+        entries.push(Ed25519SignatureEntry {
+            instruction_index,
+            pubkey: Pubkey::try_from(pubkey.as_slice()).unwrap(),
+            message,
+            signature: signature.try_into().unwrap(),
+        });
     }
 
-    Ok(())
+    Ok(entries)
+}
+
+/// Load `size` bytes at `offset` of the instruction at `instruction_index`
+/// (u16::MAX = the Ed25519Program instruction itself)
+fn load_ed25519_field(
+    sysvar_account: &AccountInfo,
+    current_data: &[u8],
+    instruction_index: u16,
+    offset: u16,
+    size: u16,
+) -> Result<Vec<u8>, ProgramError> {
+    let start = offset as usize;
+    let end = start + size as usize;
+
+    let field = if instruction_index == CURRENT_INSTRUCTION {
+        current_data.get(start..end).map(|field| field.to_vec())
+    } else {
+        let ix = load_instruction_at_checked(instruction_index as usize, sysvar_account)?;
+        ix.data.get(start..end).map(|field| field.to_vec())
+    };
+
+    field.ok_or_else(|| {
+        msg!("Ed25519Program offsets point outside of instruction data");
+        PaymentChannelError::InvalidEd25519Instruction.into()
+    })
 }
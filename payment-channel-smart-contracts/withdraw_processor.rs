@@ -0,0 +1,250 @@
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+
+use payment_channel_token::{TokenPayload, WithdrawTokenData, ACTION_WITHDRAW};
+
+use crate::channel_state::ChannelState;
+use crate::error::PaymentChannelError;
+use crate::events::ChannelEvent;
+use crate::stakeholder_state::StakeholderState;
+use crate::token_vault::{
+    check_token_account, check_token_program, check_vault, is_token_channel, vault_amount,
+    withdraw_from_vault,
+};
+use crate::try_from_slice_unchecked;
+use crate::verify_signature_processor::verify_ed25519;
+
+pub fn withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    withdraw_token_encoded: Vec<u8>,
+) -> ProgramResult {
+    // Get Account iterator
+    let account_info_iter = &mut accounts.iter();
+
+    // Get accounts
+    let msg_sender = next_account_info(account_info_iter)?;
+    let pda_channel_account = next_account_info(account_info_iter)?;
+    let pda_stakeholder_account = next_account_info(account_info_iter)?;
+    let sysvar_account = next_account_info(account_info_iter)?;
+    // Oracle PDAs (registry entries), one per signature in `sig_oracles`, same order
+    // Only for SPL Token denominated channels: vault_pda, stakeholder token account, token program
+
+    let withdraw_token_decoded =
+        match TokenPayload::try_from_slice(withdraw_token_encoded.as_slice()) {
+            Ok(payload) => payload,
+            Err(_) => {
+                msg!("Failed to decode withdraw_token");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    let withdraw_token_core_data_decoded =
+        match WithdrawTokenData::try_from_slice(withdraw_token_decoded.encoded_data.as_slice()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to decode withdraw_token_core_data");
+                return Err(PaymentChannelError::InvalidTokenEncoding.into());
+            }
+        };
+
+    if withdraw_token_core_data_decoded.action != ACTION_WITHDRAW {
+        msg!("Wrong 'action' defined within provided token.");
+        return Err(PaymentChannelError::WrongAction.into());
+    }
+
+    // Load and check PDA_CHANNEL / CHANNEL ACCOUNT
+    let channel_id = withdraw_token_core_data_decoded.channelid.as_str();
+    let (pda_channel, _) = Pubkey::find_program_address(&[channel_id.as_bytes()], program_id);
+
+    if pda_channel != *pda_channel_account.key {
+        msg!("pda_channel != pda_channel_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_channel_account_data =
+        match try_from_slice_unchecked::<ChannelState>(&pda_channel_account.data.borrow()) {
+            Ok(data) => data,
+            Err(_) => {
+                msg!("Failed to deserealize PDA_CHANNEL_ACCOUNT_DATA; Channel does not exists");
+                return Err(PaymentChannelError::AccountNotInitialized.into());
+            }
+        };
+
+    // Load and check PDA_STAKEHOLDER / STAKEHOLDER ACCOUNT
+    let (pda_stakeholder, _) = Pubkey::find_program_address(
+        &[channel_id.as_bytes(), msg_sender.key.as_ref()],
+        program_id,
+    );
+
+    if pda_stakeholder != *pda_stakeholder_account.key {
+        msg!("pda_stakeholder != pda_stakeholder_account.key; invalid seeds");
+        return Err(PaymentChannelError::InvalidSeeds.into());
+    }
+
+    let mut pda_stakeholder_account_data = match try_from_slice_unchecked::<StakeholderState>(
+        &pda_stakeholder_account.data.borrow(),
+    ) {
+        Ok(data) => data,
+        Err(_) => {
+            msg!("Failed to deserialize Stakeholder PDA data. Stakeholder (msg.sender) is not part of the channel");
+            return Err(PaymentChannelError::AccountNotInitialized.into());
+        }
+    };
+
+    let pda_oracle_accounts =
+        next_account_infos(account_info_iter, withdraw_token_decoded.sig_oracles.len())?;
+
+    // Checks (Channel status = opened, Stakeholder status = active, channelid matches, amount > 0, stakeholder sender)
+    if pda_channel_account_data.channel_id != channel_id {
+        msg!("withdraw_token.channel_id != channel.channel_id");
+        return Err(PaymentChannelError::ChannelIdMismatch.into());
+    }
+
+    pda_channel_account_data.check_channel_nonce(withdraw_token_core_data_decoded.channel_nonce)?;
+
+    if pda_channel_account_data.current_status != 1 {
+        msg!("Channel is not opened");
+        return Err(PaymentChannelError::ChannelNotOpened.into());
+    }
+
+    if pda_stakeholder_account_data.status != 2 {
+        msg!("Stakeholder status != active");
+        return Err(PaymentChannelError::InvalidStakeholderStatus.into());
+    }
+
+    // Sender should be the same as defined within token
+    if *msg_sender.key != withdraw_token_core_data_decoded.sender
+        || *msg_sender.key != withdraw_token_core_data_decoded.address
+    {
+        msg!("Sender of this TX is not the same as defined in token");
+        return Err(PaymentChannelError::SenderMismatch.into());
+    }
+
+    let amount = withdraw_token_core_data_decoded.amount;
+
+    if amount == 0 {
+        msg!("Provided amount is too low");
+        return Err(PaymentChannelError::InvalidAmount.into());
+    }
+
+    if withdraw_token_core_data_decoded.sequence <= pda_channel_account_data.sequence {
+        msg!(
+            "Token sequence {} is not greater than channel sequence {}",
+            withdraw_token_core_data_decoded.sequence,
+            pda_channel_account_data.sequence
+        );
+        return Err(PaymentChannelError::StaleSequence.into());
+    }
+
+    // Verification of 'sender' signature - stakeholder must have signed the token data too
+    msg!("Verification of token signature (sig_sender)");
+    match verify_ed25519(
+        sysvar_account,
+        withdraw_token_core_data_decoded.sender, // pub key
+        withdraw_token_decoded.sender_preimage().to_vec(), // msg
+        withdraw_token_core_data_decoded.sig_sender, // sig
+    ) {
+        Ok(_) => msg!("Sender signature succesfuly verified!"),
+        Err(_) => {
+            msg!("Verification of withdraw_token (sig_sender) FAILED");
+            return Err(PaymentChannelError::InvalidSenderSignature.into());
+        }
+    }
+
+    // Verification of 'oracle' signatures (M-of-N)
+    msg!("Verification of token signatures (sig_oracles)");
+    pda_channel_account_data.oracle_quorum().verify(
+        program_id,
+        pda_oracle_accounts,
+        sysvar_account,
+        withdraw_token_decoded.oracle_preimage(),
+        &withdraw_token_decoded.sig_oracles,
+        false,
+    )?;
+
+    let token_channel = is_token_channel(&pda_channel_account_data.mint);
+
+    if token_channel {
+        let vault_account = next_account_info(account_info_iter)?;
+        let stakeholder_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        check_token_program(token_program)?;
+        check_vault(program_id, pda_channel_account.key, vault_account)?;
+        check_token_account(
+            stakeholder_token_account,
+            &pda_channel_account_data.mint,
+            msg_sender.key,
+        )?;
+
+        if vault_amount(vault_account)? < amount {
+            msg!("Channel vault does not hold enough tokens");
+            return Err(PaymentChannelError::InsufficientFunds.into());
+        }
+
+        msg!("amount (tokens) to be transfered: {}", amount);
+        withdraw_from_vault(
+            program_id,
+            channel_id,
+            vault_account,
+            stakeholder_token_account,
+            pda_channel_account,
+            token_program,
+            amount,
+        )?;
+    } else {
+        // Channel PDA stays rent exempt
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(pda_channel_account.data_len());
+        let channel_funds = pda_channel_account
+            .lamports()
+            .saturating_sub(rent_exempt_lamports);
+
+        if channel_funds < amount {
+            msg!("Channel does not hold enough lamports");
+            return Err(PaymentChannelError::InsufficientFunds.into());
+        }
+    }
+
+    // assign values (stakeholder stays active)
+    pda_stakeholder_account_data.balance = withdraw_token_core_data_decoded.balance;
+    pda_stakeholder_account_data.sequence = withdraw_token_core_data_decoded.sequence;
+
+    // serialize
+    pda_stakeholder_account_data
+        .serialize(&mut &mut pda_stakeholder_account.data.borrow_mut()[..])?;
+
+    // assign values
+    pda_channel_account_data.sequence = withdraw_token_core_data_decoded.sequence;
+    pda_channel_account_data.last_checkpoint_slot = Clock::get()?.slot;
+    pda_channel_account_data.total_deposits = pda_channel_account_data
+        .total_deposits
+        .saturating_sub(amount);
+
+    // serialize
+    pda_channel_account_data.serialize(&mut &mut pda_channel_account.data.borrow_mut()[..])?;
+
+    if !token_channel {
+        msg!("amount to be transfered: {}", amount);
+
+        **pda_channel_account.try_borrow_mut_lamports()? -= amount;
+        **msg_sender.try_borrow_mut_lamports()? += amount;
+    }
+
+    ChannelEvent::Withdrawn {
+        channel_id: channel_id.to_string(),
+        stakeholder: *msg_sender.key,
+        amount,
+        sequence: withdraw_token_core_data_decoded.sequence,
+    }
+    .emit();
+
+    Ok(())
+}
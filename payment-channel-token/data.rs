@@ -0,0 +1,81 @@
+use alloc::{string::String, vec::Vec};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::payload::SignedTokenData;
+use crate::Address;
+
+// action = 1
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct OpenTokenData {
+    pub action: u8,
+    pub channelid: String,
+    pub address: Address,
+    pub balance: u64,
+    pub sender: Address,
+    pub sequence: u64,
+    pub channel_nonce: u64,    // incarnation of the channel id (nonce_pda)
+    pub mint: Address,         // Pubkey::default() = native SOL
+    pub oracles: Vec<Address>, // oracle set of the channel
+    pub oracle_threshold: u8,  // min number of oracle signatures per token
+    pub emergency_timeout_slots: u64, // see emergency_exit_processor
+    pub sig_sender: [u8; 64],
+}
+
+// action = 2 (join), 4 (leave), 5 (deposit)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct BalanceTokenData {
+    pub action: u8,
+    pub channelid: String,
+    pub address: Address,
+    pub balance: u64, // joined / final / deposited (added on top of current balance) amount
+    pub sender: Address,
+    pub sequence: u64,
+    pub channel_nonce: u64,
+    pub sig_sender: [u8; 64],
+}
+
+pub type JoinTokenData = BalanceTokenData;
+pub type LeaveTokenData = BalanceTokenData;
+pub type DepositTokenData = BalanceTokenData;
+
+// action = 6
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct WithdrawTokenData {
+    pub action: u8,
+    pub channelid: String,
+    pub address: Address,
+    pub balance: u64, // balance remaining in the channel after withdrawal
+    pub amount: u64,  // withdrawn amount
+    pub sender: Address,
+    pub sequence: u64,
+    pub channel_nonce: u64,
+    pub sig_sender: [u8; 64],
+}
+
+// action = 7; produced and signed by the oracles only (no sig_sender)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct RootTokenData {
+    pub action: u8,
+    pub channelid: String,
+    // Merkle root of (address, balance, sequence) leaves, see merkle.rs of the program
+    pub root: [u8; 32],
+    pub total_balance: u64, // sum of all leaf balances
+    pub sequence: u64,
+    pub channel_nonce: u64,
+}
+
+// action = 8; produced and signed by the oracles only (no sig_sender). Binds the IPFS path a
+// checkpointed state is published under to that state (see checkpoint_processor of the program)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct CheckpointTokenData {
+    pub action: u8,
+    pub channelid: String,
+    pub state_hash: [u8; 32], // sha256 of the encoded_data of the checkpointed (action = 3) token
+    pub sequence: u64,        // sequence of the checkpointed token
+    pub channel_nonce: u64,
+    pub cid: String, // IPFS path, e.g. "/ipfs/<cid>"
+}
+
+impl SignedTokenData for OpenTokenData {}
+impl SignedTokenData for BalanceTokenData {}
+impl SignedTokenData for WithdrawTokenData {}
@@ -0,0 +1,35 @@
+// Token formats of the payment channel: Borsh encoding/decoding and signing preimages.
+// no_std (alloc only), shared by the on-chain program and off-chain tooling. With the `solana`
+// feature addresses are solana_program Pubkeys, otherwise plain 32 byte arrays (same encoding).
+#![no_std]
+
+extern crate alloc;
+
+pub mod data;
+pub mod payload;
+pub mod update;
+
+pub use data::{
+    BalanceTokenData, CheckpointTokenData, DepositTokenData, JoinTokenData, LeaveTokenData,
+    OpenTokenData, RootTokenData, WithdrawTokenData,
+};
+pub use payload::{OracleSignature, SignedTokenData, TokenPayload};
+pub use update::UpdateTokenData;
+
+#[cfg(feature = "solana")]
+pub type Address = solana_program::pubkey::Pubkey;
+#[cfg(not(feature = "solana"))]
+pub type Address = [u8; 32];
+
+// `action` byte of the token core data
+pub const ACTION_OPEN: u8 = 1;
+pub const ACTION_JOIN: u8 = 2;
+pub const ACTION_UPDATE: u8 = 3;
+pub const ACTION_LEAVE: u8 = 4;
+pub const ACTION_DEPOSIT: u8 = 5;
+pub const ACTION_WITHDRAW: u8 = 6;
+pub const ACTION_BALANCES_ROOT: u8 = 7;
+pub const ACTION_CHECKPOINT: u8 = 8;
+
+// Length of sig_sender, the last field of every token signed by a stakeholder
+pub const SIG_SENDER_LEN: usize = 64;
@@ -0,0 +1,20 @@
+// Build check that the token codec stays no_std (without the `solana` feature):
+//
+//     cargo build --manifest-path payment-channel-token/no-std-check/Cargo.toml
+//
+// Once anything in the dependency graph links std, std's panic handler clashes with the one
+// below ("duplicate lang item `panic_impl`") and the build fails. Kept out of the workspace, as
+// the workspace build unifies the `solana` feature in.
+#![no_std]
+
+use core::panic::PanicInfo;
+
+pub use payment_channel_token::{
+    BalanceTokenData, CheckpointTokenData, OpenTokenData, RootTokenData, TokenPayload,
+    UpdateTokenData, WithdrawTokenData,
+};
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
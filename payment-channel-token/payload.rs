@@ -0,0 +1,57 @@
+use alloc::{string::String, vec::Vec};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::SIG_SENDER_LEN;
+
+// Oracle signature within token payloads, tagged with the version of the key that produced it
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct OracleSignature {
+    pub key_version: u32,
+    pub signature: [u8; 64],
+}
+
+// Token as passed to the program: core data (see data.rs / update.rs) signed by the oracles,
+// linked to the previous state (IPFS path, "0" for open)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct TokenPayload {
+    pub encoded_data: Vec<u8>,
+    pub prev_state: String,
+    pub sig_oracles: Vec<OracleSignature>, // M-of-N, see OracleQuorum
+}
+
+impl TokenPayload {
+    // Oracles sign the whole core data (sig_sender included)
+    pub fn oracle_preimage(&self) -> &[u8] {
+        &self.encoded_data
+    }
+
+    // Stakeholder signs the core data without sig_sender (last 64 bytes)
+    pub fn sender_preimage(&self) -> &[u8] {
+        &self.encoded_data[..self.encoded_data.len().saturating_sub(SIG_SENDER_LEN)]
+    }
+
+    // sig_sender as carried at the end of the core data
+    pub fn sig_sender(&self) -> Option<[u8; 64]> {
+        let start = self.encoded_data.len().checked_sub(SIG_SENDER_LEN)?;
+        let mut sig_sender = [0u8; 64];
+        sig_sender.copy_from_slice(&self.encoded_data[start..]);
+
+        Some(sig_sender)
+    }
+}
+
+// Core data signed by a stakeholder; `sig_sender` is always the last field
+pub trait SignedTokenData: BorshSerialize {
+    // Bytes the stakeholder signs (encoded core data without sig_sender)
+    fn sender_preimage(&self) -> Vec<u8> {
+        let mut data = self.try_to_vec().unwrap();
+        data.truncate(data.len().saturating_sub(SIG_SENDER_LEN));
+
+        data
+    }
+
+    // Bytes the oracles sign (= TokenPayload::encoded_data)
+    fn oracle_preimage(&self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
+}
@@ -0,0 +1,209 @@
+// Golden tests against the tokens recorded by the JS oracle during the experiments
+// (experiment/off-chain-txs-data/*-test-data.json, one decoded token per line).
+//
+// The fixtures are in the layout of the first program version: no `sequence` / `channel_nonce`,
+// a single oracle signature, open tokens without the channel configuration. They are signed with
+// the experiment keys, so they can not be regenerated in the current layout. The raw token bytes
+// are rebuilt from the recorded fields; the stakeholder and oracle signatures verifying over them
+// pins them byte-for-byte to what the JS side produced. The codec types must reject every one of
+// them, so a legacy token is never misread as a current one.
+
+use std::fs;
+use std::path::PathBuf;
+
+use borsh::BorshDeserialize;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde_json::Value;
+
+use payment_channel_token::{
+    BalanceTokenData, OpenTokenData, TokenPayload, UpdateTokenData, ACTION_JOIN, ACTION_LEAVE,
+    ACTION_OPEN, ACTION_UPDATE, SIG_SENDER_LEN,
+};
+
+// Oracle of the experiments (hard-coded in the first version of modify_oracle_processor)
+const ORACLE_PUBKEY: &str = "FXCB4QMbUFC4B8zpo7Hx4ihm3RL5mt7LQoVJbrdRiHhJ";
+
+struct Fixture {
+    name: String,
+    action: u8,
+    channel_id: String,
+    state: Vec<([u8; 32], u64)>,
+    sender: [u8; 32],
+    prev_state: String,
+    sig_sender: [u8; 64],
+    sig_oracle: [u8; 64],
+}
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../experiment/off-chain-txs-data")
+}
+
+fn pubkey(value: &str) -> [u8; 32] {
+    bs58::decode(value).into_vec().unwrap().try_into().unwrap()
+}
+
+// Signatures are recorded as {"0": byte, ..., "63": byte}
+fn signature(value: &Value) -> [u8; 64] {
+    let mut signature = [0u8; 64];
+    for (i, byte) in signature.iter_mut().enumerate() {
+        *byte = value[i.to_string()].as_u64().unwrap() as u8;
+    }
+
+    signature
+}
+
+fn load_fixtures() -> Vec<Fixture> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with("-test-data.json"))
+        .collect();
+    paths.sort();
+
+    let mut fixtures = Vec::new();
+    for path in paths {
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        for (line_number, line) in fs::read_to_string(&path).unwrap().lines().enumerate() {
+            // Files also contain the odd console line of the experiment scripts
+            if !line.starts_with('{') {
+                continue;
+            }
+
+            let value: Value = serde_json::from_str(line).unwrap();
+
+            fixtures.push(Fixture {
+                name: format!("{}:{}", file_name, line_number + 1),
+                action: value["action"].as_u64().unwrap() as u8,
+                channel_id: value["channel_id"].as_str().unwrap().to_string(),
+                state: value["state"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|entry| {
+                        (
+                            pubkey(entry["address"].as_str().unwrap()),
+                            entry["balance"].as_u64().unwrap(),
+                        )
+                    })
+                    .collect(),
+                sender: pubkey(value["sender"].as_str().unwrap()),
+                prev_state: value["state_prev"].as_str().unwrap().to_string(),
+                sig_sender: signature(&value["sender_sig"]),
+                sig_oracle: signature(&value["oracle_sig"]),
+            });
+        }
+    }
+
+    assert!(!fixtures.is_empty(), "no fixtures found");
+
+    fixtures
+}
+
+// Borsh string / Vec<u8>: u32 length prefix followed by the bytes
+fn extend_len_prefixed(data: &mut Vec<u8>, bytes: &[u8]) {
+    data.extend((bytes.len() as u32).to_le_bytes());
+    data.extend(bytes);
+}
+
+// Core data (encoded_data) as produced by the JS oracle
+fn legacy_encoded_data(fixture: &Fixture) -> Vec<u8> {
+    let mut data = vec![fixture.action];
+    extend_len_prefixed(&mut data, fixture.channel_id.as_bytes());
+
+    match fixture.action {
+        // action, channelid, address, balance, sender, sig_sender
+        ACTION_OPEN | ACTION_JOIN | ACTION_LEAVE => {
+            assert_eq!(fixture.state.len(), 1, "{}", fixture.name);
+
+            data.extend(fixture.state[0].0);
+            data.extend(fixture.state[0].1.to_le_bytes());
+            data.extend(fixture.sender);
+        }
+        // action, channelid, (addressN, balanceN)*, sender, num_of_stakeholders, sig_sender
+        ACTION_UPDATE => {
+            for (address, balance) in fixture.state.iter() {
+                data.extend(address);
+                data.extend(balance.to_le_bytes());
+            }
+            data.extend(fixture.sender);
+            data.push(fixture.state.len() as u8);
+        }
+        action => panic!("{}: unexpected action {}", fixture.name, action),
+    }
+    data.extend(fixture.sig_sender);
+
+    data
+}
+
+// Token as passed to the program: encoded_data, prev_state, sig_oracle
+fn legacy_token(fixture: &Fixture) -> Vec<u8> {
+    let mut data = Vec::new();
+    extend_len_prefixed(&mut data, &legacy_encoded_data(fixture));
+    extend_len_prefixed(&mut data, fixture.prev_state.as_bytes());
+    data.extend(fixture.sig_oracle);
+
+    data
+}
+
+fn verify(pubkey: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    PublicKey::from_bytes(pubkey)
+        .unwrap()
+        .verify(message, &Signature::try_from(&signature[..]).unwrap())
+        .is_ok()
+}
+
+#[test]
+fn sender_signatures_verify_over_raw_tokens() {
+    for fixture in load_fixtures() {
+        let encoded_data = legacy_encoded_data(&fixture);
+        let preimage = &encoded_data[..encoded_data.len() - SIG_SENDER_LEN];
+
+        assert!(
+            verify(&fixture.sender, preimage, &fixture.sig_sender),
+            "{}: sig_sender does not verify over the raw token",
+            fixture.name
+        );
+    }
+}
+
+#[test]
+fn oracle_signatures_verify_over_raw_tokens() {
+    let oracle = pubkey(ORACLE_PUBKEY);
+
+    for fixture in load_fixtures() {
+        // Oracle signs the whole core data (sig_sender included)
+        assert!(
+            verify(&oracle, &legacy_encoded_data(&fixture), &fixture.sig_oracle),
+            "{}: sig_oracle does not verify over the raw token",
+            fixture.name
+        );
+    }
+}
+
+#[test]
+fn codec_rejects_legacy_core_data() {
+    for fixture in load_fixtures() {
+        let encoded_data = legacy_encoded_data(&fixture);
+
+        let rejected = match fixture.action {
+            ACTION_OPEN => OpenTokenData::try_from_slice(&encoded_data).is_err(),
+            ACTION_JOIN | ACTION_LEAVE => BalanceTokenData::try_from_slice(&encoded_data).is_err(),
+            _ => UpdateTokenData::try_from_slice(&encoded_data).is_err(),
+        };
+
+        assert!(rejected, "{}: legacy core data decoded", fixture.name);
+    }
+}
+
+#[test]
+fn codec_rejects_legacy_tokens() {
+    for fixture in load_fixtures() {
+        // sig_oracle is no Vec<OracleSignature>
+        assert!(
+            TokenPayload::try_from_slice(&legacy_token(&fixture)).is_err(),
+            "{}: legacy token decoded",
+            fixture.name
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Tokens produced by the current JS oracle (payment-channel-service/oracle.js) for open, join,
+// update and leave of a channel, see experiment/generate-oracle-fixtures.js. Every token must
+// decode into the codec types and encode back to the very same bytes, and the stakeholder and
+// oracle signatures must verify over the preimages of the codec, so any drift between the JS and
+// the Rust layout fails here. Regenerate the fixtures whenever a token layout changes.
+
+use std::fs;
+use std::path::PathBuf;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde_json::Value;
+
+use payment_channel_token::{
+    Address, BalanceTokenData, OpenTokenData, SignedTokenData, TokenPayload, UpdateTokenData,
+    ACTION_JOIN, ACTION_LEAVE, ACTION_OPEN, ACTION_UPDATE,
+};
+
+struct Fixtures {
+    oracle: [u8; 32],
+    channel_id: String,
+    tokens: Vec<(u8, Vec<u8>)>, // (action, token bytes)
+}
+
+fn load_fixtures() -> Fixtures {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../experiment/off-chain-txs-data/oracle-fixtures.json");
+    let value: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+    let tokens: Vec<(u8, Vec<u8>)> = value["tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|token| {
+            (
+                token["action"].as_u64().unwrap() as u8,
+                hex(token["token"].as_str().unwrap()),
+            )
+        })
+        .collect();
+
+    assert!(!tokens.is_empty(), "no fixtures found");
+
+    Fixtures {
+        oracle: bs58::decode(value["oracle"].as_str().unwrap())
+            .into_vec()
+            .unwrap()
+            .try_into()
+            .unwrap(),
+        channel_id: value["channel_id"].as_str().unwrap().to_string(),
+        tokens,
+    }
+}
+
+fn hex(value: &str) -> Vec<u8> {
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn verify(pubkey: &[u8], message: &[u8], signature: &[u8; 64]) -> bool {
+    PublicKey::from_bytes(pubkey)
+        .unwrap()
+        .verify(message, &Signature::try_from(&signature[..]).unwrap())
+        .is_ok()
+}
+
+// Core data fields the checks below need, taken from the decoded codec type
+struct Core {
+    channelid: String,
+    sender: Address,
+    sequence: u64,
+    channel_nonce: u64,
+    sender_preimage: Vec<u8>,
+    oracle_preimage: Vec<u8>,
+}
+
+impl Core {
+    fn new<T: SignedTokenData>(
+        data: &T,
+        channelid: &str,
+        sender: Address,
+        sequence: u64,
+        channel_nonce: u64,
+    ) -> Self {
+        Core {
+            channelid: channelid.to_string(),
+            sender,
+            sequence,
+            channel_nonce,
+            sender_preimage: data.sender_preimage(),
+            oracle_preimage: data.oracle_preimage(),
+        }
+    }
+}
+
+// Decodes the core data with the codec type of the action and checks it encodes back byte-for-byte
+fn decode_core(action: u8, encoded_data: &[u8]) -> Core {
+    match action {
+        ACTION_OPEN => {
+            let data = OpenTokenData::try_from_slice(encoded_data).unwrap();
+            assert_eq!(data.try_to_vec().unwrap(), encoded_data);
+
+            Core::new(
+                &data,
+                &data.channelid,
+                data.sender,
+                data.sequence,
+                data.channel_nonce,
+            )
+        }
+        ACTION_JOIN | ACTION_LEAVE => {
+            let data = BalanceTokenData::try_from_slice(encoded_data).unwrap();
+            assert_eq!(data.try_to_vec().unwrap(), encoded_data);
+
+            Core::new(
+                &data,
+                &data.channelid,
+                data.sender,
+                data.sequence,
+                data.channel_nonce,
+            )
+        }
+        ACTION_UPDATE => {
+            let data = UpdateTokenData::try_from_slice(encoded_data).unwrap();
+            assert_eq!(data.try_to_vec().unwrap(), encoded_data);
+
+            Core::new(
+                &data,
+                &data.channelid,
+                data.sender,
+                data.sequence,
+                data.channel_nonce,
+            )
+        }
+        action => panic!("unexpected action {}", action),
+    }
+}
+
+#[test]
+fn tokens_roundtrip_byte_for_byte() {
+    let fixtures = load_fixtures();
+
+    for (action, token) in fixtures.tokens.iter() {
+        let payload = TokenPayload::try_from_slice(token).unwrap();
+        assert_eq!(payload.try_to_vec().unwrap(), *token, "action {}", action);
+
+        let core = decode_core(*action, &payload.encoded_data);
+        assert_eq!(core.channelid, fixtures.channel_id, "action {}", action);
+        assert_eq!(payload.encoded_data[0], *action);
+    }
+}
+
+#[test]
+fn tokens_carry_sequence_and_channel_nonce() {
+    let fixtures = load_fixtures();
+
+    let mut last_sequence = 0;
+    for (action, token) in fixtures.tokens.iter() {
+        let payload = TokenPayload::try_from_slice(token).unwrap();
+        let core = decode_core(*action, &payload.encoded_data);
+
+        // Oracle hands out the next sequence of the channel to every token
+        assert_eq!(core.sequence, last_sequence + 1, "action {}", action);
+        last_sequence = core.sequence;
+
+        // All tokens belong to the first incarnation of the channel id
+        assert_eq!(core.channel_nonce, 1, "action {}", action);
+
+        let expected_prev_state = if *action == ACTION_OPEN || *action == ACTION_JOIN {
+            "0"
+        } else {
+            "/ipfs/"
+        };
+        assert!(
+            payload.prev_state.starts_with(expected_prev_state),
+            "action {}: prev_state {}",
+            action,
+            payload.prev_state
+        );
+    }
+}
+
+#[test]
+fn signatures_verify_over_codec_preimages() {
+    let fixtures = load_fixtures();
+
+    for (action, token) in fixtures.tokens.iter() {
+        let payload = TokenPayload::try_from_slice(token).unwrap();
+        let core = decode_core(*action, &payload.encoded_data);
+
+        assert_eq!(core.sender_preimage, payload.sender_preimage());
+        assert_eq!(core.oracle_preimage, payload.oracle_preimage());
+        assert!(
+            verify(
+                core.sender.as_ref(),
+                payload.sender_preimage(),
+                &payload.sig_sender().unwrap()
+            ),
+            "action {}: sig_sender does not verify",
+            action
+        );
+
+        assert_eq!(payload.sig_oracles.len(), 1, "action {}", action);
+        for sig_oracle in payload.sig_oracles.iter() {
+            assert_eq!(sig_oracle.key_version, 0);
+            assert!(
+                verify(
+                    &fixtures.oracle,
+                    payload.oracle_preimage(),
+                    &sig_oracle.signature
+                ),
+                "action {}: sig_oracle does not verify",
+                action
+            );
+        }
+    }
+}
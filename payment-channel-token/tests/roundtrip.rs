@@ -0,0 +1,232 @@
+// Every token type: encode -> decode -> encode is the identity, and the signing preimages are
+// the encoding with (sender) and without (oracles) the trailing sig_sender.
+
+use std::fmt::Debug;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use payment_channel_token::{
+    Address, BalanceTokenData, CheckpointTokenData, OpenTokenData, OracleSignature, RootTokenData,
+    SignedTokenData, TokenPayload, UpdateTokenData, WithdrawTokenData, ACTION_BALANCES_ROOT,
+    ACTION_CHECKPOINT, ACTION_DEPOSIT, ACTION_JOIN, ACTION_LEAVE, ACTION_OPEN, ACTION_UPDATE,
+    ACTION_WITHDRAW, SIG_SENDER_LEN,
+};
+
+const SIG_SENDER: [u8; 64] = [9; 64];
+
+// Address of either kind (plain bytes, or Pubkey with the `solana` feature)
+fn address(byte: u8) -> Address {
+    Address::from([byte; 32])
+}
+
+fn roundtrip<T: BorshSerialize + BorshDeserialize + Debug + PartialEq>(token: &T) -> Vec<u8> {
+    let encoded = token.try_to_vec().unwrap();
+    let decoded = T::try_from_slice(&encoded).unwrap();
+
+    assert_eq!(&decoded, token);
+    assert_eq!(decoded.try_to_vec().unwrap(), encoded);
+
+    encoded
+}
+
+fn check_preimages<T: SignedTokenData>(token: &T, encoded: &[u8]) {
+    assert_eq!(token.oracle_preimage(), encoded);
+    assert_eq!(
+        token.sender_preimage(),
+        &encoded[..encoded.len() - SIG_SENDER_LEN]
+    );
+    assert_eq!(&encoded[encoded.len() - SIG_SENDER_LEN..], &SIG_SENDER[..]);
+}
+
+fn update_token(state: Vec<(Address, u64)>) -> UpdateTokenData {
+    UpdateTokenData {
+        action: ACTION_UPDATE,
+        channelid: "channel".to_string(),
+        num_of_stakeholders: state.len() as u8,
+        state,
+        sender: address(1),
+        sequence: 7,
+        channel_nonce: 2,
+        sig_sender: SIG_SENDER,
+    }
+}
+
+#[test]
+fn open_token_roundtrips() {
+    let token = OpenTokenData {
+        action: ACTION_OPEN,
+        channelid: "channel".to_string(),
+        address: address(1),
+        balance: 100,
+        sender: address(1),
+        sequence: 1,
+        channel_nonce: 1,
+        mint: address(0),
+        oracles: vec![address(2), address(3)],
+        oracle_threshold: 2,
+        emergency_timeout_slots: 1000,
+        sig_sender: SIG_SENDER,
+    };
+
+    let encoded = roundtrip(&token);
+    check_preimages(&token, &encoded);
+}
+
+#[test]
+fn balance_tokens_roundtrip() {
+    for action in [ACTION_JOIN, ACTION_LEAVE, ACTION_DEPOSIT] {
+        let token = BalanceTokenData {
+            action,
+            channelid: "channel".to_string(),
+            address: address(2),
+            balance: 50,
+            sender: address(2),
+            sequence: 3,
+            channel_nonce: 1,
+            sig_sender: SIG_SENDER,
+        };
+
+        let encoded = roundtrip(&token);
+        check_preimages(&token, &encoded);
+    }
+}
+
+#[test]
+fn withdraw_token_roundtrips() {
+    let token = WithdrawTokenData {
+        action: ACTION_WITHDRAW,
+        channelid: "channel".to_string(),
+        address: address(2),
+        balance: 40,
+        amount: 10,
+        sender: address(2),
+        sequence: 4,
+        channel_nonce: 1,
+        sig_sender: SIG_SENDER,
+    };
+
+    let encoded = roundtrip(&token);
+    check_preimages(&token, &encoded);
+}
+
+#[test]
+fn oracle_only_tokens_roundtrip() {
+    roundtrip(&RootTokenData {
+        action: ACTION_BALANCES_ROOT,
+        channelid: "channel".to_string(),
+        root: [5; 32],
+        total_balance: 150,
+        sequence: 5,
+        channel_nonce: 1,
+    });
+
+    roundtrip(&CheckpointTokenData {
+        action: ACTION_CHECKPOINT,
+        channelid: "channel".to_string(),
+        state_hash: [6; 32],
+        sequence: 5,
+        channel_nonce: 1,
+        cid: "/ipfs/cid".to_string(),
+    });
+}
+
+#[test]
+fn update_token_roundtrips() {
+    for state in [
+        vec![],
+        vec![(address(1), 10)],
+        vec![(address(1), 10), (address(2), 20), (address(3), 30)],
+    ] {
+        let token = update_token(state);
+
+        let encoded = token.try_to_vec().unwrap();
+        let decoded = UpdateTokenData::try_from_slice(&encoded).unwrap();
+
+        assert_eq!(decoded, token);
+        assert_eq!(decoded.try_to_vec().unwrap(), encoded);
+        check_preimages(&token, &encoded);
+    }
+}
+
+#[test]
+fn update_token_layout_is_flat() {
+    let token = update_token(vec![(address(1), 10), (address(2), 20)]);
+    let encoded = token.try_to_vec().unwrap();
+
+    // action, channelid, (addressN, balanceN)*, sender, sequence, channel_nonce,
+    // num_of_stakeholders, sig_sender
+    let mut expected = vec![ACTION_UPDATE];
+    expected.extend(7u32.to_le_bytes());
+    expected.extend(b"channel");
+    expected.extend_from_slice(address(1).as_ref());
+    expected.extend(10u64.to_le_bytes());
+    expected.extend_from_slice(address(2).as_ref());
+    expected.extend(20u64.to_le_bytes());
+    expected.extend_from_slice(address(1).as_ref());
+    expected.extend(7u64.to_le_bytes());
+    expected.extend(2u64.to_le_bytes());
+    expected.push(2);
+    expected.extend(SIG_SENDER);
+
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn malformed_update_token_is_rejected() {
+    let encoded = update_token(vec![(address(1), 10), (address(2), 20)])
+        .try_to_vec()
+        .unwrap();
+    let num_of_stakeholders_at = encoded.len() - SIG_SENDER_LEN - 1;
+
+    // num_of_stakeholders does not match the number of pairs
+    for num_of_stakeholders in [1, 3] {
+        let mut data = encoded.clone();
+        data[num_of_stakeholders_at] = num_of_stakeholders;
+        assert!(UpdateTokenData::try_from_slice(&data).is_err());
+    }
+
+    // Trailing byte, truncated data
+    let mut data = encoded.clone();
+    data.push(0);
+    assert!(UpdateTokenData::try_from_slice(&data).is_err());
+    assert!(UpdateTokenData::try_from_slice(&encoded[1..]).is_err());
+    assert!(UpdateTokenData::try_from_slice(&encoded[..SIG_SENDER_LEN]).is_err());
+
+    // num_of_stakeholders has to match the state when encoding as well
+    let mut token = update_token(vec![(address(1), 10)]);
+    token.num_of_stakeholders = 2;
+    assert!(token.try_to_vec().is_err());
+}
+
+#[test]
+fn token_payload_roundtrips() {
+    let token = update_token(vec![(address(1), 10), (address(2), 20)]);
+
+    let payload = TokenPayload {
+        encoded_data: token.try_to_vec().unwrap(),
+        prev_state: "/ipfs/prev".to_string(),
+        sig_oracles: vec![
+            OracleSignature {
+                key_version: 0,
+                signature: [7; 64],
+            },
+            OracleSignature {
+                key_version: 3,
+                signature: [8; 64],
+            },
+        ],
+    };
+
+    roundtrip(&payload);
+
+    assert_eq!(payload.oracle_preimage(), token.oracle_preimage());
+    assert_eq!(payload.sender_preimage(), token.sender_preimage());
+    assert_eq!(payload.sig_sender(), Some(SIG_SENDER));
+
+    let short = TokenPayload {
+        encoded_data: vec![0; SIG_SENDER_LEN - 1],
+        prev_state: "0".to_string(),
+        sig_oracles: vec![],
+    };
+    assert_eq!(short.sig_sender(), None);
+}
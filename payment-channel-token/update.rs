@@ -0,0 +1,97 @@
+use alloc::{string::String, vec::Vec};
+use borsh::maybestd::io::{Error, ErrorKind, Result as IoResult, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::payload::SignedTokenData;
+use crate::{Address, SIG_SENDER_LEN};
+
+// Off-chain tx token (action = 3) as produced by the oracle (see `oracle.js` -> `update`)
+// encoded_data: action, channelid, (addressN, balanceN)*, sender, sequence, channel_nonce,
+// num_of_stakeholders, sig_sender
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateTokenData {
+    pub action: u8,
+    pub channelid: String,
+    pub state: Vec<(Address, u64)>, // (address1, balance1) ... (addressN, balanceN)
+    pub sender: Address,
+    pub sequence: u64,
+    pub channel_nonce: u64,
+    pub num_of_stakeholders: u8,
+    pub sig_sender: [u8; 64],
+}
+
+impl UpdateTokenData {
+    // The number of (address, balance) pairs is only known from the `num_of_stakeholders`
+    // byte, which sits right before `sig_sender` (last 64 bytes), so it is read first.
+    pub fn try_from_slice(data: &[u8]) -> IoResult<Self> {
+        if data.len() < SIG_SENDER_LEN + 1 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+        let num_of_stakeholders = data[data.len() - SIG_SENDER_LEN - 1];
+
+        let buf = &mut &data[..];
+        let action = u8::deserialize(buf)?;
+        let channelid = String::deserialize(buf)?;
+
+        let mut state = Vec::with_capacity(num_of_stakeholders as usize);
+        for _ in 0..num_of_stakeholders {
+            let address = Address::deserialize(buf)?;
+            let balance = u64::deserialize(buf)?;
+            state.push((address, balance));
+        }
+
+        let sender = Address::deserialize(buf)?;
+        let sequence = u64::deserialize(buf)?;
+        let channel_nonce = u64::deserialize(buf)?;
+        let decoded_num_of_stakeholders = u8::deserialize(buf)?;
+        let sig_sender = <[u8; 64]>::deserialize(buf)?;
+
+        if decoded_num_of_stakeholders != num_of_stakeholders || !buf.is_empty() {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        Ok(Self {
+            action,
+            channelid,
+            state,
+            sender,
+            sequence,
+            channel_nonce,
+            num_of_stakeholders,
+            sig_sender,
+        })
+    }
+
+    pub fn balance_of(&self, address: &Address) -> Option<u64> {
+        self.state
+            .iter()
+            .find(|(state_address, _)| state_address == address)
+            .map(|(_, balance)| *balance)
+    }
+}
+
+// Pairs are written flat (no length prefix), their number goes into `num_of_stakeholders`
+impl BorshSerialize for UpdateTokenData {
+    fn serialize<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        if self.state.len() != self.num_of_stakeholders as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "num_of_stakeholders does not match state",
+            ));
+        }
+
+        self.action.serialize(writer)?;
+        self.channelid.serialize(writer)?;
+        for (address, balance) in self.state.iter() {
+            address.serialize(writer)?;
+            balance.serialize(writer)?;
+        }
+        self.sender.serialize(writer)?;
+        self.sequence.serialize(writer)?;
+        self.channel_nonce.serialize(writer)?;
+        self.num_of_stakeholders.serialize(writer)?;
+        self.sig_sender.serialize(writer)
+    }
+}
+
+impl SignedTokenData for UpdateTokenData {}